@@ -0,0 +1,594 @@
+mod domain;
+mod puzzle;
+mod scheduler;
+
+use std::{
+    env, fs,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context as _};
+use domain::{Encoding, ENCODINGS};
+use puzzle::{
+    clues::Puzzle,
+    definition::EINSTEIN,
+    generate::Planted,
+    street::{self, Street},
+};
+use scheduler::{
+    args::{flag_value, has_flag},
+    instance::Instance,
+    model::Schedule,
+};
+use z3::{Config, Context, Params, SatResult, Solver, Statistics, StatisticsValue};
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    println!(
+        "Solve time of generated scheduling instances for each room/timeslot encoding,
+        or of logic grid puzzles for each street encoding with --zebra.
+        Build with --release for meaningful numbers."
+    );
+
+    // [--scaling | --zebra] [--sizes 10,20,40] [--seed 1] [--runs 3] [--timeout 10000]
+    let scaling = has_flag(&args, "--scaling");
+    let sizes: Vec<usize> = flag_value(&args, "--sizes")
+        .unwrap_or_else(|| if scaling { "100,200,400" } else { "10,20,40" }.to_string())
+        .split(',')
+        .map(|n| n.trim().parse().map_err(|_| anyhow!("bad size `{}`", n)))
+        .collect::<anyhow::Result<_>>()?;
+    let seed: u64 = match flag_value(&args, "--seed") {
+        Some(seed) => seed.parse().map_err(|_| anyhow!("bad --seed `{}`", seed))?,
+        None => 1,
+    };
+    let runs: u32 = match flag_value(&args, "--runs") {
+        Some(runs) => runs.parse().map_err(|_| anyhow!("bad --runs `{}`", runs))?,
+        None => 3,
+    };
+    let timeout = match flag_value(&args, "--timeout") {
+        Some(ms) => Duration::from_millis(
+            ms.parse()
+                .map_err(|_| anyhow!("bad --timeout `{}`, in ms", ms))?,
+        ),
+        None => Duration::from_secs(if scaling { 60 } else { 10 }),
+    };
+
+    if scaling {
+        return scale(&args, &sizes, seed, runs, timeout);
+    }
+    if has_flag(&args, "--zebra") {
+        return zebra(&args, seed, runs, timeout);
+    }
+
+    println!(
+        "{:>8} {:>6} {:>6} {:>9} | {}",
+        "meetings",
+        "slots",
+        "rooms",
+        "rules",
+        ENCODINGS
+            .map(|e| format!("{:>10}", e.to_string()))
+            .join(" ")
+    );
+    for &size in &sizes {
+        let instance = sized(seed, size)?;
+
+        let mut times = Vec::new();
+        for encoding in ENCODINGS {
+            times.push(match average(&instance, encoding, runs, timeout)? {
+                Some(run) => format!("{:>8.1}ms", run.elapsed.as_secs_f64() * 1000.0),
+                None => format!("{:>10}", "timeout"),
+            });
+        }
+
+        println!(
+            "{:>8} {:>6} {:>6} {:>9} | {}",
+            instance.meetings,
+            instance.slots,
+            instance.rooms,
+            instance.rules.len(),
+            times.join(" ")
+        );
+    }
+
+    Ok(())
+}
+
+/// One encoding on large instances: time, memory and conflicts per size,
+/// optionally saved to or compared against a CSV of earlier times
+fn scale(
+    args: &[String],
+    sizes: &[usize],
+    seed: u64,
+    runs: u32,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    // [--encoding bv] [--save times.csv] [--baseline times.csv] [--threshold 1.5]
+    let encoding = match flag_value(args, "--encoding") {
+        Some(encoding) => encoding.parse()?,
+        None => Encoding::BitVec,
+    };
+    let threshold: f64 = match flag_value(args, "--threshold") {
+        Some(t) => t.parse().map_err(|_| anyhow!("bad --threshold `{}`", t))?,
+        None => 1.5,
+    };
+    let baseline = match flag_value(args, "--baseline") {
+        Some(path) => parse_times(
+            &fs::read_to_string(&path).with_context(|| format!("cannot read {}", path))?,
+        )?,
+        None => Vec::new(),
+    };
+
+    println!("Encoding: {}", encoding);
+    println!(
+        "{:>8} {:>6} {:>6} {:>6} | {:>10} {:>10} {:>10}",
+        "meetings", "slots", "rooms", "rules", "time", "memory", "conflicts"
+    );
+    let mut times = Vec::new();
+    for &size in sizes {
+        let instance = large(seed, size)?;
+
+        let line = format!(
+            "{:>8} {:>6} {:>6} {:>6} |",
+            instance.meetings,
+            instance.slots,
+            instance.rooms,
+            instance.rules.len()
+        );
+        match average(&instance, encoding, runs, timeout)? {
+            Some(run) => {
+                let ms = run.elapsed.as_secs_f64() * 1000.0;
+                println!(
+                    "{} {:>8.1}ms {:>8.1}MB {:>10}",
+                    line, ms, run.memory, run.conflicts
+                );
+                times.push((size, ms));
+            }
+            None => {
+                println!("{} {:>10}", line, "timeout");
+                times.push((size, f64::INFINITY));
+            }
+        }
+    }
+
+    if let Some(path) = flag_value(args, "--save") {
+        fs::write(&path, format_times(&times)).with_context(|| format!("cannot write {}", path))?;
+        println!("Saved times to {}", path);
+    }
+
+    let slower = regressions(&baseline, &times, threshold);
+    if !slower.is_empty() {
+        bail!(
+            "slower than {}x the baseline:\n{}",
+            threshold,
+            slower.join("\n")
+        );
+    }
+
+    Ok(())
+}
+
+/// Every street encoding on the Einstein puzzle and on generated puzzles of growing size:
+/// time, conflicts, decisions and memory
+fn zebra(args: &[String], seed: u64, runs: u32, timeout: Duration) -> anyhow::Result<()> {
+    // [--houses 6,8,10] [--categories 5]
+    let houses: Vec<usize> = flag_value(args, "--houses")
+        .unwrap_or_else(|| "6,8,10".to_string())
+        .split(',')
+        .map(|n| {
+            n.trim()
+                .parse()
+                .map_err(|_| anyhow!("bad house count `{}`", n))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let categories: usize = match flag_value(args, "--categories") {
+        Some(n) => n.parse().map_err(|_| anyhow!("bad --categories `{}`", n))?,
+        None => 5,
+    };
+
+    let mut puzzles: Vec<(String, Puzzle)> = vec![("einstein".to_string(), EINSTEIN.parse()?)];
+    for &size in &houses {
+        let planted = planted(seed, size, categories)?;
+        puzzles.push((format!("{} houses x {}", size, categories), planted.puzzle));
+    }
+
+    for (name, puzzle) in &puzzles {
+        println!("{}, {} clues", name, puzzle.clues.len());
+        println!(
+            "{:>10} | {:>10} {:>10} {:>10} {:>10}",
+            "encoding", "time", "conflicts", "decisions", "memory"
+        );
+        for encoding in street::ENCODINGS {
+            match average_puzzle(puzzle, encoding, runs, timeout)? {
+                Some(run) => println!(
+                    "{:>10} | {:>8.1}ms {:>10} {:>10} {:>8.1}MB",
+                    encoding.to_string(),
+                    run.elapsed.as_secs_f64() * 1000.0,
+                    run.conflicts,
+                    run.decisions,
+                    run.memory
+                ),
+                None => println!("{:>10} | {:>10}", encoding.to_string(), "timeout"),
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// A puzzle with a clue per house and category, enough to leave few solutions
+fn planted(seed: u64, houses: usize, categories: usize) -> anyhow::Result<Planted> {
+    Planted::generate(seed, houses, categories, houses * categories)
+}
+
+/// A fairly tight instance: 80% of the slot x room cells in use, two rules per meeting
+fn sized(seed: u64, meetings: usize) -> anyhow::Result<Instance> {
+    let rooms = (meetings / 8).max(2);
+    let slots = (meetings * 5 / 4).div_ceil(rooms);
+    Instance::generate(seed, meetings, slots, rooms, meetings * 2)
+}
+
+/// Scaling instances: a room per ten meetings, half of the cells in use, two rules per meeting
+fn large(seed: u64, meetings: usize) -> anyhow::Result<Instance> {
+    let rooms = (meetings / 10).max(2);
+    let slots = (meetings * 2).div_ceil(rooms);
+    Instance::generate(seed, meetings, slots, rooms, meetings * 2)
+}
+
+/// `meetings,ms` lines under a header, `timeout` for an infinite time
+fn format_times(times: &[(usize, f64)]) -> String {
+    let mut csv = "meetings,ms\n".to_string();
+    for &(meetings, ms) in times {
+        let ms = match ms.is_finite() {
+            true => format!("{:.1}", ms),
+            false => "timeout".to_string(),
+        };
+        csv.push_str(&format!("{},{}\n", meetings, ms));
+    }
+    csv
+}
+
+/// `20.0ms`, or `timeout`
+fn ms_text(ms: f64) -> String {
+    if ms.is_finite() {
+        format!("{:.1}ms", ms)
+    } else {
+        "timeout".to_string()
+    }
+}
+
+fn parse_times(csv: &str) -> anyhow::Result<Vec<(usize, f64)>> {
+    csv.lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (meetings, ms) = line
+                .split_once(',')
+                .ok_or_else(|| anyhow!("expected `meetings,ms`, got `{}`", line))?;
+            Ok((
+                meetings
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("bad meetings in `{}`", line))?,
+                match ms.trim() {
+                    "timeout" => f64::INFINITY,
+                    ms => ms.parse().map_err(|_| anyhow!("bad ms in `{}`", line))?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Sizes of the baseline that got more than `threshold` times slower, timed out
+/// (unless they already did) or were not measured
+fn regressions(baseline: &[(usize, f64)], times: &[(usize, f64)], threshold: f64) -> Vec<String> {
+    baseline
+        .iter()
+        .filter_map(|&(meetings, before)| {
+            let now = match times.iter().find(|(size, _)| *size == meetings) {
+                Some(&(_, ms)) if ms > before * threshold => ms_text(ms),
+                Some(_) => return None,
+                None => "not measured".to_string(),
+            };
+            Some(format!(
+                "{} meetings: {}, was {}",
+                meetings,
+                now,
+                ms_text(before)
+            ))
+        })
+        .collect()
+}
+
+struct Run {
+    elapsed: Duration,
+    outcome: Outcome,
+    /// Peak memory in MB as z3 reports it
+    memory: f64,
+    /// SAT and SMT core conflicts
+    conflicts: u64,
+}
+
+enum Outcome {
+    /// (slot, room) of each meeting
+    Solved(Vec<(usize, usize)>),
+    Unsat,
+    /// Gave up at the timeout
+    Unknown,
+}
+
+/// Mean over `runs` solves, None if any timed out. Bails on wrong answers since the
+/// instance is known to be feasible.
+fn average(
+    instance: &Instance,
+    encoding: Encoding,
+    runs: u32,
+    timeout: Duration,
+) -> anyhow::Result<Option<Run>> {
+    let mut total = Run {
+        elapsed: Duration::ZERO,
+        outcome: Outcome::Unknown,
+        memory: 0.0,
+        conflicts: 0,
+    };
+    for _ in 0..runs {
+        let run = solve(instance, encoding, timeout);
+        match &run.outcome {
+            Outcome::Solved(assignment) => {
+                if !instance.check(assignment) {
+                    bail!("{} returned an invalid schedule", encoding);
+                }
+            }
+            Outcome::Unknown => return Ok(None),
+            Outcome::Unsat => bail!("{} says a feasible instance is unsat", encoding),
+        }
+        total.elapsed += run.elapsed;
+        total.memory = total.memory.max(run.memory);
+        total.conflicts += run.conflicts;
+        total.outcome = run.outcome;
+    }
+
+    total.elapsed /= runs.max(1);
+    total.conflicts /= runs.max(1) as u64;
+    Ok(Some(total))
+}
+
+/// Wall clock time of building and checking the model
+fn solve(instance: &Instance, encoding: Encoding, timeout: Duration) -> Run {
+    let start = Instant::now();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
+    let mut params = Params::new(&ctx);
+    params.set_u32("timeout", timeout.as_millis().min(u32::MAX as u128) as u32);
+    solver.set_params(&params);
+
+    let schedule = Schedule::sized(
+        &ctx,
+        instance.meetings,
+        instance.slots,
+        instance.rooms,
+        encoding,
+    );
+    for constraint in instance.constraints(&schedule) {
+        solver.assert(&constraint);
+    }
+
+    let outcome = match solver.check() {
+        SatResult::Sat => {
+            let model = solver.get_model().unwrap();
+            Outcome::Solved(
+                schedule
+                    .slot
+                    .iter()
+                    .zip(&schedule.room)
+                    .map(|(slot, room)| {
+                        (
+                            schedule.slots.decode(&model, slot),
+                            schedule.rooms.decode(&model, room),
+                        )
+                    })
+                    .collect(),
+            )
+        }
+        SatResult::Unsat => Outcome::Unsat,
+        SatResult::Unknown => Outcome::Unknown,
+    };
+    let elapsed = start.elapsed();
+
+    let statistics = solver.get_statistics();
+    Run {
+        elapsed,
+        outcome,
+        memory: number(&statistics, "max memory"),
+        conflicts: (number(&statistics, "conflicts") + number(&statistics, "sat conflicts")) as u64,
+    }
+}
+
+/// A solved puzzle and what it took
+struct PuzzleRun {
+    elapsed: Duration,
+    memory: f64,
+    conflicts: u64,
+    decisions: u64,
+}
+
+/// Mean over `runs` solves, None if any timed out. Bails on unsat or on a grid breaking
+/// a clue, the puzzles here all have a solution.
+fn average_puzzle(
+    puzzle: &Puzzle,
+    encoding: street::Encoding,
+    runs: u32,
+    timeout: Duration,
+) -> anyhow::Result<Option<PuzzleRun>> {
+    let mut total = PuzzleRun {
+        elapsed: Duration::ZERO,
+        memory: 0.0,
+        conflicts: 0,
+        decisions: 0,
+    };
+    for _ in 0..runs {
+        let Some(run) = solve_puzzle(puzzle, encoding, timeout)? else {
+            return Ok(None);
+        };
+        total.elapsed += run.elapsed;
+        total.memory = total.memory.max(run.memory);
+        total.conflicts += run.conflicts;
+        total.decisions += run.decisions;
+    }
+
+    total.elapsed /= runs.max(1);
+    total.conflicts /= runs.max(1) as u64;
+    total.decisions /= runs.max(1) as u64;
+    Ok(Some(total))
+}
+
+/// Wall clock time of building and checking the street, None on timeout
+fn solve_puzzle(
+    puzzle: &Puzzle,
+    encoding: street::Encoding,
+    timeout: Duration,
+) -> anyhow::Result<Option<PuzzleRun>> {
+    let start = Instant::now();
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
+    let mut params = Params::new(&ctx);
+    params.set_u32("timeout", timeout.as_millis().min(u32::MAX as u128) as u32);
+    solver.set_params(&params);
+
+    let street = Street::new(&ctx, &puzzle.definition, encoding);
+    for constraint in street.constraints() {
+        solver.assert(&constraint);
+    }
+    for clue in &puzzle.clues {
+        for constraint in clue.constraints(&street) {
+            solver.assert(&constraint);
+        }
+    }
+
+    let solution = match solver.check() {
+        SatResult::Sat => street.decode(&solver.get_model().unwrap()),
+        SatResult::Unsat => bail!("{} says a puzzle with a solution is unsat", encoding),
+        SatResult::Unknown => return Ok(None),
+    };
+    let elapsed = start.elapsed();
+
+    for clue in &puzzle.clues {
+        if !clue.holds(&solution) {
+            bail!("{} breaks `{}`", encoding, clue.text(&puzzle.definition));
+        }
+    }
+
+    let statistics = solver.get_statistics();
+    Ok(Some(PuzzleRun {
+        elapsed,
+        memory: number(&statistics, "max memory"),
+        conflicts: (number(&statistics, "conflicts") + number(&statistics, "sat conflicts")) as u64,
+        decisions: (number(&statistics, "decisions") + number(&statistics, "sat decisions")) as u64,
+    }))
+}
+
+/// A z3 statistic as a number, 0 when the solver did not report it
+fn number(statistics: &Statistics, key: &str) -> f64 {
+    match statistics.value(key) {
+        Some(StatisticsValue::UInt(n)) => n as f64,
+        Some(StatisticsValue::Double(n)) => n,
+        None => 0.0,
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_bench_encodings() {
+    let instance = sized(3, 24).unwrap();
+
+    for encoding in ENCODINGS {
+        match solve(&instance, encoding, Duration::from_secs(60)).outcome {
+            Outcome::Solved(assignment) => assert!(instance.check(&assignment), "{}", encoding),
+            _ => panic!("{} did not solve a feasible instance", encoding),
+        }
+    }
+
+    // Every meeting apart from every other needs a slot each
+    let mut instance = Instance::generate(1, 4, 3, 2, 0).unwrap();
+    instance.rules = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]
+        .map(|(i, j)| scheduler::instance::Rule::Apart(i, j))
+        .to_vec();
+    for encoding in ENCODINGS {
+        assert!(
+            matches!(
+                solve(&instance, encoding, Duration::from_secs(60)).outcome,
+                Outcome::Unsat
+            ),
+            "{}",
+            encoding
+        );
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_bench_scaling() {
+    let instance = large(5, 60).unwrap();
+    assert_eq!((instance.slots, instance.rooms), (20, 6));
+
+    let run = average(&instance, Encoding::BitVec, 1, Duration::from_secs(60))
+        .unwrap()
+        .unwrap();
+    assert!(matches!(run.outcome, Outcome::Solved(_)));
+    assert!(run.memory > 0.0);
+
+    let times = [(100, 20.0), (200, 80.5), (300, f64::INFINITY)];
+    assert_eq!(parse_times(&format_times(&times)).unwrap(), times);
+    assert!(parse_times("meetings,ms\n100;20").is_err());
+
+    let now = [
+        (100, 25.0),
+        (200, 200.0),
+        (300, f64::INFINITY),
+        (400, 900.0),
+    ];
+    assert_eq!(
+        regressions(&times, &now, 1.5),
+        ["200 meetings: 200.0ms, was 80.5ms"]
+    );
+    assert!(regressions(&times, &now, 3.0).is_empty());
+
+    // Timing out where the baseline solved, or not measuring a size of the baseline, fails
+    let now = [(100, f64::INFINITY), (300, 10.0)];
+    assert_eq!(
+        regressions(&times, &now, 1.5),
+        [
+            "100 meetings: timeout, was 20.0ms",
+            "200 meetings: not measured, was 80.5ms"
+        ]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_bench_zebra() {
+    let einstein: Puzzle = EINSTEIN.parse().unwrap();
+    let planted = planted(2, 6, 4).unwrap();
+
+    for encoding in street::ENCODINGS {
+        for puzzle in [&einstein, &planted.puzzle] {
+            let run = solve_puzzle(puzzle, encoding, Duration::from_secs(60))
+                .unwrap()
+                .unwrap();
+            assert!(run.memory > 0.0, "{}", encoding);
+        }
+    }
+
+    // A wrong clue is caught as unsat
+    let mut broken = einstein.clone();
+    broken
+        .clues
+        .push(puzzle::clues::Clue::parse("Norwegian at 2", &einstein.definition).unwrap());
+    assert!(solve_puzzle(&broken, street::Encoding::OneHot, Duration::from_secs(60)).is_err());
+}
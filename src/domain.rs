@@ -0,0 +1,207 @@
+// Shared by the scheduler and the puzzles, not every bin uses every helper
+#![allow(dead_code)]
+
+use std::{fmt, str::FromStr};
+
+use anyhow::bail;
+use z3::{
+    ast::{self, Ast, Bool, Dynamic},
+    Context, FuncDecl, Model, Sort, Symbol,
+};
+
+/// How a finite domain (rooms, timeslots, the values of a puzzle category) is handed to z3
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// Unbounded `Int` plus explicit bounds
+    #[default]
+    Int,
+    /// Bit-vector just wide enough for the domain, bounded when the size is not a power of two
+    BitVec,
+    /// Enumeration datatype, one constructor per value, no bounds needed
+    Enum,
+}
+
+impl FromStr for Encoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s.trim() {
+            "int" => Encoding::Int,
+            "bv" | "bitvec" => Encoding::BitVec,
+            "enum" => Encoding::Enum,
+            other => bail!("unknown encoding `{}` (int, bv, enum)", other),
+        })
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Encoding::Int => "int",
+            Encoding::BitVec => "bv",
+            Encoding::Enum => "enum",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub const ENCODINGS: [Encoding; 3] = [Encoding::Int, Encoding::BitVec, Encoding::Enum];
+
+/// Values 0..size of one encoding, variables are `Dynamic` so callers don't care which
+pub struct Domain<'ctx> {
+    ctx: &'ctx Context,
+    pub encoding: Encoding,
+    pub size: usize,
+    sort: Sort<'ctx>,
+    values: Vec<Dynamic<'ctx>>,
+}
+
+impl<'ctx> Domain<'ctx> {
+    /// `name` names the enumeration sort, so it must be unique per context
+    pub fn new(ctx: &'ctx Context, encoding: Encoding, name: &str, size: usize) -> Self {
+        let (sort, values) = match encoding {
+            Encoding::Int => (
+                Sort::int(ctx),
+                (0..size)
+                    .map(|v| Dynamic::from_ast(&ast::Int::from_u64(ctx, v as u64)))
+                    .collect(),
+            ),
+            Encoding::BitVec => {
+                let bits = bits_for(size);
+                (
+                    Sort::bitvector(ctx, bits),
+                    (0..size)
+                        .map(|v| Dynamic::from_ast(&ast::BV::from_u64(ctx, v as u64, bits)))
+                        .collect(),
+                )
+            }
+            Encoding::Enum => {
+                let names: Vec<Symbol> = (0..size)
+                    .map(|v| Symbol::String(format!("{}{}", name, v)))
+                    .collect();
+                let (sort, constants, _testers) =
+                    Sort::enumeration(ctx, Symbol::String(name.to_string()), &names);
+                (sort, constants.iter().map(|c| c.apply(&[])).collect())
+            }
+        };
+
+        Domain {
+            ctx,
+            encoding,
+            size,
+            sort,
+            values,
+        }
+    }
+
+    pub fn var(&self, name: &str) -> Dynamic<'ctx> {
+        FuncDecl::new(self.ctx, name, &[], &self.sort).apply(&[])
+    }
+
+    /// `var` holds `value`, false for values outside the domain
+    pub fn is(&self, var: &Dynamic<'ctx>, value: usize) -> Bool<'ctx> {
+        match self.values.get(value) {
+            Some(constant) => var._eq(constant),
+            None => Bool::from_bool(self.ctx, false),
+        }
+    }
+
+    /// What keeps `var` inside 0..size, nothing for enumerations
+    pub fn bounds(&self, var: &Dynamic<'ctx>) -> Option<Bool<'ctx>> {
+        match self.encoding {
+            Encoding::Int => {
+                let var = var.as_int().unwrap();
+                Some(
+                    var.ge(&ast::Int::from_u64(self.ctx, 0))
+                        & var.lt(&ast::Int::from_u64(self.ctx, self.size as u64)),
+                )
+            }
+            Encoding::BitVec => {
+                let bits = bits_for(self.size);
+                if self.size == 1 << bits {
+                    return None;
+                }
+                let var = var.as_bv().unwrap();
+                Some(var.bvult(&ast::BV::from_u64(self.ctx, self.size as u64, bits)))
+            }
+            Encoding::Enum => None,
+        }
+    }
+
+    /// `a` holds a smaller value than `b`, native comparison where the sort has one
+    pub fn lt(&self, a: &Dynamic<'ctx>, b: &Dynamic<'ctx>) -> Bool<'ctx> {
+        match self.encoding {
+            Encoding::Int => a.as_int().unwrap().lt(&b.as_int().unwrap()),
+            Encoding::BitVec => a.as_bv().unwrap().bvult(&b.as_bv().unwrap()),
+            Encoding::Enum => self.index(a).lt(&self.index(b)),
+        }
+    }
+
+    /// Value of `var` as an integer
+    fn index(&self, var: &Dynamic<'ctx>) -> ast::Int<'ctx> {
+        (1..self.size).fold(ast::Int::from_u64(self.ctx, 0), |index, value| {
+            self.is(var, value)
+                .ite(&ast::Int::from_u64(self.ctx, value as u64), &index)
+        })
+    }
+
+    pub fn decode(&self, model: &Model<'ctx>, var: &Dynamic<'ctx>) -> usize {
+        (0..self.size)
+            .find(|&v| {
+                model
+                    .eval(&self.is(var, v), true)
+                    .and_then(|holds| holds.as_bool())
+                    .unwrap_or(false)
+            })
+            .expect("model value outside the domain")
+    }
+}
+
+/// Smallest width that can count to size - 1 (at least one bit)
+fn bits_for(size: usize) -> u32 {
+    (usize::BITS - size.saturating_sub(1).leading_zeros()).max(1)
+}
+
+#[cfg(test)]
+#[test]
+fn test_domains() {
+    use z3::{Config, SatResult, Solver};
+
+    assert_eq!(bits_for(1), 1);
+    assert_eq!(bits_for(2), 1);
+    assert_eq!(bits_for(3), 2);
+    assert_eq!(bits_for(8), 3);
+    assert_eq!(bits_for(9), 4);
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+
+    // Three distinct values out of three leave a single choice for the last one
+    for encoding in ENCODINGS {
+        let solver = Solver::new(&ctx);
+        let domain = Domain::new(&ctx, encoding, &format!("three_{}", encoding), 3);
+        let vars: Vec<Dynamic> = (0..3)
+            .map(|i| domain.var(&format!("{}_{}", encoding, i)))
+            .collect();
+
+        for var in &vars {
+            if let Some(bounds) = domain.bounds(var) {
+                solver.assert(&bounds);
+            }
+        }
+        let refs: Vec<&Dynamic> = vars.iter().collect();
+        solver.assert(&Dynamic::distinct(&ctx, &refs));
+        solver.assert(&domain.is(&vars[0], 2));
+        solver.assert(&domain.is(&vars[1], 0));
+
+        assert_eq!(solver.check(), SatResult::Sat, "{}", encoding);
+        let model = solver.get_model().unwrap();
+        assert_eq!(domain.decode(&model, &vars[2]), 1, "{}", encoding);
+
+        solver.assert(&domain.lt(&vars[1], &vars[2]));
+        solver.assert(&domain.lt(&vars[2], &vars[0]));
+        assert_eq!(solver.check(), SatResult::Sat, "{}", encoding);
+        solver.assert(&domain.lt(&vars[0], &vars[1]));
+        assert_eq!(solver.check(), SatResult::Unsat, "{}", encoding);
+    }
+}
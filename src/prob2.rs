@@ -1,14 +1,28 @@
-use z3::{
-    ast::{self, Ast},
-    Config, Context, SatResult, Solver,
+mod domain;
+mod scheduler;
+
+use std::{env, fs};
+
+use anyhow::{anyhow, bail, Context as _};
+use chrono::{Datelike, NaiveDate, Weekday};
+use chrono_tz::Tz;
+use domain::Encoding;
+use scheduler::{
+    args::{flag_value, flag_values, has_flag},
+    busy::{self, Blocked},
+    grid,
+    ics::{self, IcsConfig},
+    model::Schedule,
+    objectives::{self, Objective},
+    preferences::Preference,
+    schedule::{self, Meeting, Solution},
+    validate,
 };
+use z3::{ast, Config, Context, Optimize, SatResult, Solver};
 
-struct Solution {
-    am: bool,
-    room: i64,
-}
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
 
-fn main() {
     println!(
         "You are organizing a schedule for four meetings—M1, M2, M3, and M4—over two timeslots: Morning (AM) and Afternoon (PM).
         You have three available conference rooms: R1, R2, and R3.
@@ -27,72 +41,154 @@ fn main() {
 
     );
 
-    solve();
-}
+    // --check "PM R1, AM R1, AM R2, PM R2" validates a hand-made schedule instead of solving
+    if let Some(list) = flag_value(&args, "--check") {
+        let violations = validate::violations(&schedule::parse_solutions(&list)?)?;
+        for violation in &violations {
+            println!("{}", violation);
+        }
+        if !violations.is_empty() {
+            bail!("{} violation(s)", violations.len());
+        }
+        println!("Valid schedule");
+        return Ok(());
+    }
 
-fn solve() -> Option<Vec<Solution>> {
-    let cfg = Config::new();
-    let ctx = Context::new(&cfg);
-    let solver = Solver::new(&ctx);
+    // [--date YYYY-MM-DD] [--zone Europe/Paris] [--attendees M1=alice,bob ...]
+    // [--busy alice=alice.ics --busy R1=r1.ics ...]
+    let meetings = schedule::meetings(4, &flag_values(&args, "--attendees"))?;
 
-    // am == is AM?
-    let m1_am = ast::Bool::new_const(&ctx, "m1_am");
-    let m1_room = ast::Int::new_const(&ctx, "m1_room");
-    let m2_am = ast::Bool::new_const(&ctx, "m2_am");
-    let m2_room = ast::Int::new_const(&ctx, "m2_room");
-    let m3_am = ast::Bool::new_const(&ctx, "m3_am");
-    let m3_room = ast::Int::new_const(&ctx, "m3_room");
-    let m4_am = ast::Bool::new_const(&ctx, "m4_am");
-    let m4_room = ast::Int::new_const(&ctx, "m4_room");
-
-    // Rooms need to be in [1, 3]
-    for room in [&m1_room, &m2_room, &m3_room, &m4_room] {
-        solver.assert(&room.ge(&ast::Int::from_i64(&ctx, 1)));
-        solver.assert(&room.le(&ast::Int::from_i64(&ctx, 3)));
-    }
-
-    // No duplicates (cannot have same meeting AND same room)
-    solver.assert(&!((&m1_room._eq(&m2_room)) & (&m1_am._eq(&m2_am))));
-    solver.assert(&!((&m1_room._eq(&m3_room)) & (&m1_am._eq(&m3_am))));
-    solver.assert(&!((&m1_room._eq(&m4_room)) & (&m1_am._eq(&m4_am))));
-    solver.assert(&!((&m2_room._eq(&m3_room)) & (&m2_am._eq(&m3_am))));
-    solver.assert(&!((&m2_room._eq(&m4_room)) & (&m2_am._eq(&m4_am))));
-    solver.assert(&!((&m3_room._eq(&m4_room)) & (&m3_am._eq(&m4_am))));
-
-    // 1.	M1 and M2 cannot be held in the same timeslot.
-    solver.assert(&ast::Bool::xor(&m1_am, &m2_am));
-
-    // 2.	If M3 is scheduled in the morning, then M4 cannot be held in R1 or R3.
-    solver.assert(&m3_am.implies(&m4_room._eq(&ast::Int::from_i64(&ctx, 2))));
-
-    // 3.	If M2 is scheduled in R2, then M1 must not be in the afternoon.
-    solver.assert(&m2_room._eq(&ast::Int::from_i64(&ctx, 2)).implies(&m1_am));
-
-    // 4.   At least two of the meetings (out of M1, M2, M3, M4) must be in the afternoon timeslot.
-    solver.assert(
-        &ast::Int::add(
-            &ctx,
-            &[
-                &m1_am.ite(&ast::Int::from_i64(&ctx, 1), &ast::Int::from_i64(&ctx, 0)),
-                &m2_am.ite(&ast::Int::from_i64(&ctx, 1), &ast::Int::from_i64(&ctx, 0)),
-                &m3_am.ite(&ast::Int::from_i64(&ctx, 1), &ast::Int::from_i64(&ctx, 0)),
-                &m4_am.ite(&ast::Int::from_i64(&ctx, 1), &ast::Int::from_i64(&ctx, 0)),
-            ],
-        )
-        .ge(&ast::Int::from_i64(&ctx, 2)),
-    );
+    let mut config = IcsConfig::default();
+    if let Some(date) = flag_value(&args, "--date") {
+        config.base_date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .with_context(|| format!("bad --date `{}`", date))?;
+    }
+    if let Some(zone) = flag_value(&args, "--zone") {
+        config.zone = zone
+            .parse::<Tz>()
+            .map_err(|_| anyhow!("unknown time zone `{}`", zone))?;
+    }
+
+    let horizon = config.slot_bounds(false).1;
+    let mut busy_times = Vec::new();
+    for spec in flag_values(&args, "--busy") {
+        busy_times.extend(busy::load(&spec, horizon, config.zone)?);
+    }
+    let blocked = busy::blocked(&meetings, &busy_times, &config);
+
+    // [--minimize rooms,afternoon,early,gaps,fair,spread] [--prefer "M2 R1 5" --prefer "all !PM Fri 3" ...]
+    let objectives = match flag_value(&args, "--minimize") {
+        Some(list) => objectives::parse_list(&list)?,
+        None => Vec::new(),
+    };
+    let preferences = flag_values(&args, "--prefer")
+        .iter()
+        .map(|p| p.parse::<Preference>())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    // [--previous "PM R1, AM R1, AM R2, PM R2"] [--require "M2 !AM" ...]
+    // reschedules with as few moved meetings as possible
+    let previous = match flag_value(&args, "--previous") {
+        Some(list) => schedule::parse_solutions(&list)?,
+        None => Vec::new(),
+    };
+    if !previous.is_empty() && previous.len() != meetings.len() {
+        bail!("--previous needs one `AM Rn`/`PM Rn` per meeting");
+    }
+    let required = flag_values(&args, "--require")
+        .iter()
+        .map(|p| p.parse::<Preference>())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    if let Some(p) = preferences
+        .iter()
+        .chain(&required)
+        .find(|p| p.meeting.is_some_and(|m| m >= meetings.len()))
+    {
+        bail!("no such meeting in `{}`", p);
+    }
+
+    // [--encoding int|bv|enum] picks the z3 sort for rooms and timeslots
+    let encoding = match flag_value(&args, "--encoding") {
+        Some(encoding) => encoding.parse()?,
+        None => Encoding::default(),
+    };
+
+    // Day specific preferences and rules apply when --date falls on their day
+    let weekday = Some(config.base_date.weekday());
+
+    // --impact "M2 !AM" tells what a candidate rule would do to --previous (or to a fresh schedule)
+    if let Some(rule) = flag_value(&args, "--impact") {
+        let candidate: Preference = rule.parse()?;
+        let base = if previous.is_empty() {
+            solve_with(&blocked, encoding).ok_or_else(|| anyhow!("no schedule to start from"))?
+        } else {
+            previous
+        };
+        print_impact(
+            &rule,
+            &impact(&blocked, &required, &base, &candidate, weekday, encoding),
+        );
+        return Ok(());
+    }
+
+    let goals = Goals {
+        encoding,
+        blocked: &blocked,
+        meetings: &meetings,
+        objectives: &objectives,
+        preferences: &preferences,
+        weekday,
+        previous: &previous,
+        required: &required,
+    };
+    let solution = if objectives.is_empty()
+        && preferences.is_empty()
+        && previous.is_empty()
+        && required.is_empty()
+    {
+        solve_with(&blocked, encoding)
+    } else {
+        optimize_with(&goals).map(|optimum| optimum.solution)
+    };
+
+    // [--grid text|markdown|html] [--names]
+    if let Some(format) = flag_value(&args, "--grid") {
+        let format = format.parse::<grid::Format>()?;
+        let solution = solution
+            .as_ref()
+            .ok_or_else(|| anyhow!("no schedule to render"))?;
+        let names = has_flag(&args, "--names");
+
+        print!("{}", grid::render(&meetings, solution, format, names));
+    }
 
-    // 5.	M1 must not be in the same room as M3.
-    solver.assert(&m1_room._eq(&m3_room).not());
+    // --ics <file>
+    if let Some(path) = flag_value(&args, "--ics") {
+        let solution = solution.ok_or_else(|| anyhow!("no schedule to export"))?;
 
-    // 6.	M4 must be either in a different timeslot than M1 or, if in the same timeslot, it must be in R2.
-    solver.assert(&(m4_am._eq(&m1_am)).implies(&m4_room._eq(&ast::Int::from_i64(&ctx, 2))));
+        fs::write(&path, ics::export(&meetings, &solution, &config))?;
+        println!("Wrote {} events to {}", solution.len(), path);
+    }
+
+    Ok(())
+}
 
-    // 7.	If M3 is in R3, then M2 cannot be in the same room as M4.
-    solver.assert(&(m3_room._eq(&ast::Int::from_i64(&ctx, 3))).implies(&m2_room._eq(&m4_room)));
+/// Solve the puzzle, minus the slots taken by existing commitments
+fn solve_with(blocked: &[Blocked], encoding: Encoding) -> Option<Vec<Solution>> {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
 
-    // 8.	If M1 is scheduled in the morning, then M3 must not be scheduled in the afternoon.
-    solver.assert(&m1_am.implies(&m3_am));
+    let schedule = Schedule::new(&ctx, 4, encoding);
+    for constraint in schedule
+        .domain()
+        .iter()
+        .chain(&schedule.blocked(blocked))
+        .chain(&schedule.rules())
+    {
+        solver.assert(constraint);
+    }
 
     println!("Solving...");
 
@@ -100,34 +196,9 @@ fn solve() -> Option<Vec<Solution>> {
         println!("---- SAT ----");
 
         let model = solver.get_model().unwrap();
-        let value_am = [
-            model.eval(&m1_am, true).unwrap().as_bool().unwrap(),
-            model.eval(&m2_am, true).unwrap().as_bool().unwrap(),
-            model.eval(&m3_am, true).unwrap().as_bool().unwrap(),
-            model.eval(&m4_am, true).unwrap().as_bool().unwrap(),
-        ];
-
-        let value_room = [
-            model.eval(&m1_room, true).unwrap(),
-            model.eval(&m2_room, true).unwrap(),
-            model.eval(&m3_room, true).unwrap(),
-            model.eval(&m4_room, true).unwrap(),
-        ];
-
-        let mut result: Vec<Solution> = Vec::new();
-
-        for i in 0..4 {
-            result.push(Solution {
-                am: value_am[i],
-                room: value_room[i].as_i64().unwrap(),
-            });
-        }
+        let result = schedule.decode(&model);
 
-        println!("Model:");
-        println!("M1: AM: {}, Room: {}", result[0].am, result[0].room);
-        println!("M2: AM: {}, Room: {}", result[1].am, result[1].room);
-        println!("M3: AM: {}, Room: {}", result[2].am, result[2].room);
-        println!("M4: AM: {}, Room: {}", result[3].am, result[3].room);
+        print_solution(&result);
 
         Some(result)
     } else {
@@ -136,51 +207,527 @@ fn solve() -> Option<Vec<Solution>> {
     }
 }
 
+/// What to aim for on top of the eight rules
+#[derive(Default)]
+struct Goals<'a> {
+    encoding: Encoding,
+    blocked: &'a [Blocked],
+    meetings: &'a [Meeting],
+    /// Minimized lexicographically, first is most important
+    objectives: &'a [Objective],
+    /// Soft constraints (MaxSMT), below every objective
+    preferences: &'a [Preference],
+    /// Day the schedule takes place, for day specific preferences
+    weekday: Option<Weekday>,
+    /// Schedule to stay close to, moving as few meetings as possible comes before any objective
+    previous: &'a [Solution],
+    /// Preferences that must hold (weight is ignored)
+    required: &'a [Preference],
+}
+
+struct Optimum {
+    solution: Vec<Solution>,
+    /// Value reached for each objective
+    values: Vec<i64>,
+    /// Whether each preference was honored
+    honored: Vec<bool>,
+    /// Sum of the weights of the preferences that were not
+    penalty: u64,
+    /// Meetings that moved from the previous schedule
+    changed: Vec<usize>,
+}
+
+/// Best schedule for the goals
+fn optimize_with(goals: &Goals) -> Option<Optimum> {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let optimize = Optimize::new(&ctx);
+
+    let schedule = Schedule::new(&ctx, 4, goals.encoding);
+    for constraint in schedule
+        .domain()
+        .iter()
+        .chain(&schedule.blocked(goals.blocked))
+        .chain(&schedule.rules())
+    {
+        optimize.assert(constraint);
+    }
+    for requirement in goals.required {
+        optimize.assert(&requirement.constraint(&schedule, goals.weekday));
+    }
+
+    // Rescheduling: the fewer meetings move, the better
+    if !goals.previous.is_empty() {
+        let moved: Vec<ast::Int> = schedule
+            .moved(goals.previous)
+            .iter()
+            .map(|moved| moved.ite(&ast::Int::from_i64(&ctx, 1), &ast::Int::from_i64(&ctx, 0)))
+            .collect();
+        optimize.minimize(&ast::Int::add(&ctx, &moved.iter().collect::<Vec<_>>()));
+    }
+
+    // z3 combines several objectives lexicographically by default (opt.priority=lex),
+    // the first one registered wins
+    let terms: Vec<ast::Int> = goals
+        .objectives
+        .iter()
+        .map(|objective| objective.term(&schedule, goals.meetings))
+        .collect();
+    for term in &terms {
+        optimize.minimize(term);
+    }
+
+    // Soft constraints all land in the same MaxSMT objective, registered last
+    let preferences: Vec<ast::Bool> = goals
+        .preferences
+        .iter()
+        .map(|preference| preference.constraint(&schedule, goals.weekday))
+        .collect();
+    for (preference, constraint) in goals.preferences.iter().zip(&preferences) {
+        optimize.assert_soft(constraint, preference.weight, None);
+    }
+
+    println!("Optimizing...");
+
+    if optimize.check(&[]) == SatResult::Sat {
+        println!("---- SAT ----");
+
+        let model = optimize.get_model().unwrap();
+        let solution = schedule.decode(&model);
+        let values: Vec<i64> = terms
+            .iter()
+            .map(|term| model.eval(term, true).unwrap().as_i64().unwrap())
+            .collect();
+        let honored: Vec<bool> = preferences
+            .iter()
+            .map(|constraint| model.eval(constraint, true).unwrap().as_bool().unwrap())
+            .collect();
+        let penalty = goals
+            .preferences
+            .iter()
+            .zip(&honored)
+            .filter(|(_, &honored)| !honored)
+            .map(|(preference, _)| preference.weight)
+            .sum();
+
+        let changed = schedule::changed(goals.previous, &solution);
+
+        let optimum = Optimum {
+            solution,
+            values,
+            honored,
+            penalty,
+            changed,
+        };
+        print_optimum(goals, &optimum);
+
+        Some(optimum)
+    } else {
+        println!("---- UNSAT ----");
+        None
+    }
+}
+
+fn print_optimum(goals: &Goals, optimum: &Optimum) {
+    print_solution(&optimum.solution);
+
+    if !optimum.values.is_empty() {
+        println!("Objectives:");
+        for (objective, value) in goals.objectives.iter().zip(&optimum.values) {
+            println!("{}: {}", objective, value);
+        }
+    }
+
+    let loads = objectives::loads(goals.meetings, &optimum.solution);
+    if !loads.is_empty() {
+        println!("Load:");
+        for load in &loads {
+            println!("{}", load);
+        }
+        let afternoons = loads.iter().map(|load| load.afternoon);
+        println!(
+            "Afternoon meetings per person: max {}, spread {}",
+            afternoons.clone().max().unwrap(),
+            afternoons.clone().max().unwrap() - afternoons.min().unwrap()
+        );
+    }
+
+    if !optimum.honored.is_empty() {
+        println!("Preferences:");
+        for (preference, &honored) in goals.preferences.iter().zip(&optimum.honored) {
+            println!("[{}] {}", if honored { "x" } else { " " }, preference);
+        }
+        println!("Total penalty: {}", optimum.penalty);
+    }
+
+    if !goals.previous.is_empty() {
+        println!("Changes ({} moved):", optimum.changed.len());
+        for (i, (before, after)) in goals.previous.iter().zip(&optimum.solution).enumerate() {
+            if optimum.changed.contains(&i) {
+                println!("M{}: {} -> {}", i + 1, before, after);
+            } else {
+                println!("M{}: {} (unchanged)", i + 1, after);
+            }
+        }
+    }
+}
+
+/// What adopting a candidate rule does to a schedule
+struct Impact {
+    /// The schedule already follows the candidate, nothing has to move
+    fits: bool,
+    /// Some schedule follows the rules and the candidate
+    feasible: bool,
+    /// Meetings that move in every schedule following the candidate
+    must: Vec<usize>,
+    /// Meetings that move in some of them but not all
+    might: Vec<usize>,
+}
+
+/// One solver, one push/pop per question: can meeting i stay, can it move.
+/// Day specific rules only apply on `weekday`, as in `optimize_with`.
+fn impact(
+    blocked: &[Blocked],
+    required: &[Preference],
+    base: &[Solution],
+    candidate: &Preference,
+    weekday: Option<Weekday>,
+    encoding: Encoding,
+) -> Impact {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
+
+    let schedule = Schedule::new(&ctx, base.len(), encoding);
+    for constraint in schedule
+        .domain()
+        .iter()
+        .chain(&schedule.blocked(blocked))
+        .chain(&schedule.rules())
+    {
+        solver.assert(constraint);
+    }
+    for requirement in required {
+        solver.assert(&requirement.constraint(&schedule, weekday));
+    }
+    solver.assert(&candidate.constraint(&schedule, weekday));
+
+    let mut impact = Impact {
+        fits: false,
+        feasible: solver.check() == SatResult::Sat,
+        must: Vec::new(),
+        might: Vec::new(),
+    };
+    if !impact.feasible {
+        return impact;
+    }
+
+    let moved = schedule.moved(base);
+    solver.push();
+    for m in &moved {
+        solver.assert(&m.not());
+    }
+    impact.fits = solver.check() == SatResult::Sat;
+    solver.pop(1);
+
+    for (i, m) in moved.iter().enumerate() {
+        solver.push();
+        solver.assert(&m.not());
+        let can_stay = solver.check() == SatResult::Sat;
+        solver.pop(1);
+
+        solver.push();
+        solver.assert(m);
+        let can_move = solver.check() == SatResult::Sat;
+        solver.pop(1);
+
+        match (can_stay, can_move) {
+            (false, _) => impact.must.push(i),
+            (true, true) => impact.might.push(i),
+            (true, false) => {}
+        }
+    }
+
+    impact
+}
+
+fn print_impact(candidate: &str, impact: &Impact) {
+    let names = |meetings: &[usize]| -> String {
+        if meetings.is_empty() {
+            return "none".to_string();
+        }
+        let names: Vec<String> = meetings.iter().map(|i| format!("M{}", i + 1)).collect();
+        names.join(", ")
+    };
+
+    println!("Impact of `{}`:", candidate);
+    if !impact.feasible {
+        println!("No schedule can follow it together with the current rules");
+        return;
+    }
+    if impact.fits {
+        println!("The current schedule already follows it");
+    } else {
+        println!("The current schedule breaks it");
+    }
+    println!("Must move in every schedule: {}", names(&impact.must));
+    println!("Might move: {}", names(&impact.might));
+}
+
+fn print_solution(result: &[Solution]) {
+    println!("Model:");
+    for (i, meeting) in result.iter().enumerate() {
+        println!("M{}: AM: {}, Room: {}", i + 1, meeting.am, meeting.room);
+    }
+}
+
 #[cfg(test)]
 #[test]
 fn test_meeting2() {
-    let solution = solve();
+    let solution = solve_with(&[], Encoding::Int);
 
     assert!(solution.is_some());
+    assert_eq!(validate::violations(&solution.unwrap()).unwrap(), []);
+}
 
-    // All unique
-    for i in 0..4 {
-        for j in 0..4 {
-            if i != j {
-                assert!(
-                    solution.as_ref().unwrap()[i].am != solution.as_ref().unwrap()[j].am
-                        || solution.as_ref().unwrap()[i].room != solution.as_ref().unwrap()[j].room
-                );
-            }
-        }
+#[test]
+fn test_meeting2_encodings() {
+    for encoding in domain::ENCODINGS {
+        let solution = solve_with(&[], encoding).unwrap();
+        assert_eq!(validate::violations(&solution).unwrap(), [], "{}", encoding);
+
+        // Optimizing goes through the same encoding
+        let optimum = optimize_with(&Goals {
+            encoding,
+            objectives: &[Objective::Rooms],
+            ..Goals::default()
+        })
+        .unwrap();
+        assert_eq!(optimum.values, [2], "{}", encoding);
     }
+}
 
-    // 1.	M1 and M2 cannot be held in the same timeslot.
-    assert!(solution.as_ref().unwrap()[0].am != solution.as_ref().unwrap()[1].am);
-    // 2.	If M3 is scheduled in the morning, then M4 cannot be held in R1 or R3.
-    assert!(solution.as_ref().unwrap()[2].am && solution.as_ref().unwrap()[3].room == 2);
-    // 3.	If M2 is scheduled in R2, then M1 must not be in the afternoon.
-    assert!(solution.as_ref().unwrap()[1].room != 2 || solution.as_ref().unwrap()[0].am);
-    // 4.	At least two of the meetings (out of M1, M2, M3, M4) must be in the afternoon timeslot.
-    let mut count = 0;
-    for i in 0..4 {
-        if solution.as_ref().unwrap()[i].am {
-            count += 1;
-        }
+#[test]
+fn test_meeting2_ics_round_trip() {
+    let solution = solve_with(&[], Encoding::Int).unwrap();
+    let meetings = schedule::meetings(
+        4,
+        &["M1=Alice,Bob".to_string(), "M3=Charlie, Dana Smith".to_string()],
+    )
+    .unwrap();
+    let config = IcsConfig {
+        base_date: NaiveDate::from_ymd_opt(2025, 3, 10).unwrap(),
+        ..IcsConfig::default()
+    };
+
+    // One file per process, concurrent test runs don't read each other's
+    let path = env::temp_dir().join(format!("meeting2_round_trip_{}.ics", std::process::id()));
+    fs::write(&path, ics::export(&meetings, &solution, &config)).unwrap();
+    let events = ics::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(events, ics::events(&meetings, &solution, &config));
+
+    for (event, (meeting, assignment)) in events.iter().zip(meetings.iter().zip(&solution)) {
+        assert_eq!(event.summary, meeting.name);
+        assert_eq!(event.location, Some(schedule::room_name(assignment.room)));
+        assert_eq!(event.attendees, meeting.attendees);
+        assert_eq!(event.start, config.slot_bounds(assignment.am).0);
+        assert_eq!(event.end, config.slot_bounds(assignment.am).1);
     }
-    assert!(count >= 2);
-    // 5.	M1 must not be in the same room as M3.
-    assert!(solution.as_ref().unwrap()[0].room != solution.as_ref().unwrap()[2].room);
-    // 6.	M4 must be either in a different timeslot than M1 or, if in the same timeslot, it must be in R2.
-    assert!(
-        solution.as_ref().unwrap()[3].am != solution.as_ref().unwrap()[0].am
-            || solution.as_ref().unwrap()[3].room == 2
-    );
-    // 7.	If M3 is in R3, then M2 cannot be in the same room as M4.
-    assert!(
-        solution.as_ref().unwrap()[2].room != 3
-            || solution.as_ref().unwrap()[1].room != solution.as_ref().unwrap()[3].room
+}
+
+#[test]
+fn test_meeting2_busy_times() {
+    let config = IcsConfig::default();
+    let meetings = schedule::meetings(4, &["M2=Alice,Bob".to_string()]).unwrap();
+
+    // Alice has a weekly stand-up every Monday morning since last year, R1 is
+    // booked on the afternoon of the base date (a Monday)
+    let standup = ics::parse(
+        "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART:20241202T093000\r\n\
+         DTEND:20241202T100000\r\nRRULE:FREQ=WEEKLY;BYDAY=MO\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+    )
+    .unwrap();
+    let horizon = config.slot_bounds(false).1;
+    let mut busy_times = busy::expand(&busy::Owner::parse("alice"), &standup, horizon);
+    busy_times.push(busy::Busy {
+        owner: busy::Owner::parse("R1"),
+        start: config.slot_bounds(false).0,
+        end: config.slot_bounds(false).1,
+    });
+
+    let blocked = busy::blocked(&meetings, &busy_times, &config);
+    assert_eq!(
+        blocked,
+        [
+            Blocked::Meeting {
+                meeting: 1,
+                am: true
+            },
+            Blocked::Room { room: 1, am: false }
+        ]
     );
-    // 8.	If M1 is scheduled in the morning, then M3 must not be scheduled in the afternoon.
-    assert!(solution.as_ref().unwrap()[0].am || solution.as_ref().unwrap()[2].am);
+
+    let solution = solve_with(&blocked, Encoding::Int).unwrap();
+    assert!(!solution[1].am);
+    assert!(solution.iter().all(|s| s.am || s.room != 1));
+    assert_eq!(validate::violations(&solution).unwrap(), []);
+}
+
+#[test]
+fn test_meeting2_lexicographic_objectives() {
+    let meetings = schedule::meetings(4, &[]).unwrap();
+
+    // Rule 4 wants two afternoon meetings, two per slot then fit in two rooms
+    for objectives in [
+        [Objective::Afternoon, Objective::Rooms],
+        [Objective::Rooms, Objective::Afternoon],
+    ] {
+        let optimum = optimize_with(&Goals {
+            meetings: &meetings,
+            objectives: &objectives,
+            ..Goals::default()
+        })
+        .unwrap();
+        assert_eq!(optimum.values, [2, 2]);
+        assert_eq!(validate::violations(&optimum.solution).unwrap(), []);
+
+        let mut rooms: Vec<i64> = optimum.solution.iter().map(|s| s.room).collect();
+        rooms.sort();
+        rooms.dedup();
+        assert_eq!(rooms.len(), 2);
+    }
+
+    // Rooms are fixed by M4 (R2) and M1/M3, blocking R2 in the morning keeps M4 out of it
+    let blocked = [Blocked::Room { room: 2, am: true }];
+    let optimum = optimize_with(&Goals {
+        blocked: &blocked,
+        meetings: &meetings,
+        objectives: &[Objective::Afternoon, Objective::Rooms],
+        ..Goals::default()
+    })
+    .unwrap();
+    assert_eq!(optimum.values[0], 2);
+    assert!(optimum.solution.iter().all(|s| !s.am || s.room != 2));
+}
+
+#[test]
+fn test_meeting2_fair_load() {
+    let attendees = [
+        "M1=carol".to_string(),
+        "M3=carol".into(),
+        "M2=dave".into(),
+        "M4=dave".into(),
+    ];
+    let meetings = schedule::meetings(4, &attendees).unwrap();
+
+    // Only PM AM AM PM gives carol and dave one afternoon meeting each,
+    // any other schedule leaves one of them with two
+    for objective in [Objective::Fair, Objective::Spread] {
+        let optimum = optimize_with(&Goals {
+            meetings: &meetings,
+            objectives: &[objective],
+            ..Goals::default()
+        })
+        .unwrap();
+        let am: Vec<bool> = optimum.solution.iter().map(|s| s.am).collect();
+        assert_eq!(am, [false, true, true, false], "{}", objective);
+        assert_eq!(validate::violations(&optimum.solution).unwrap(), []);
+
+        let loads = objectives::loads(&meetings, &optimum.solution);
+        assert_eq!(loads.len(), 2);
+        assert!(loads
+            .iter()
+            .all(|load| load.meetings == 2 && load.afternoon == 1));
+        assert_eq!(
+            optimum.values,
+            [if objective == Objective::Fair { 1 } else { 0 }]
+        );
+    }
+}
+
+#[test]
+fn test_meeting2_impact() {
+    let base = schedule::parse_solutions("PM R1, AM R1, AM R2, PM R2").unwrap();
+    let impact_on = |candidate: &str, weekday| {
+        impact(
+            &[],
+            &[],
+            &base,
+            &candidate.parse().unwrap(),
+            Some(weekday),
+            Encoding::Int,
+        )
+    };
+    let impact_of = |candidate: &str| impact_on(candidate, Weekday::Mon);
+
+    // With M2 in the afternoon the slots are AM PM AM PM, rule 2 then keeps M4 in R2
+    let m2_pm = impact_of("M2 !AM");
+    assert!(m2_pm.feasible && !m2_pm.fits);
+    assert_eq!(m2_pm.must, [0, 1]);
+    assert_eq!(m2_pm.might, [2]);
+
+    let m4_r2 = impact_of("M4 R2");
+    assert!(m4_r2.fits);
+    assert!(m4_r2.must.is_empty());
+    assert_eq!(m4_r2.might, [0, 1, 2, 3]);
+
+    // Two slots, a single room, four meetings
+    assert!(!impact_of("all R3").feasible);
+
+    // A Friday rule moves the same meetings on a Friday, and nothing on a Monday
+    let fri = impact_on("M2 !AM Fri", Weekday::Fri);
+    assert!(!fri.fits);
+    assert_eq!(fri.must, [0, 1]);
+    let mon = impact_on("M2 !AM Fri", Weekday::Mon);
+    assert!(mon.fits && mon.must.is_empty());
+}
+
+#[test]
+fn test_meeting2_soft_preferences() {
+    let preferences: Vec<Preference> = [
+        "M1 AM 3",
+        "M2 AM 2",
+        "all !PM mon",
+        "M2 R1 4",
+        "M3 R3 Fri 9",
+    ]
+    .iter()
+    .map(|p| p.parse().unwrap())
+    .collect();
+
+    // M1 and M2 never share a slot: the heavier M1 gets the morning, and
+    // someone has to be in the afternoon on Monday. Friday is not today.
+    let optimum = optimize_with(&Goals {
+        preferences: &preferences,
+        weekday: Some(Weekday::Mon),
+        ..Goals::default()
+    })
+    .unwrap();
+
+    assert_eq!(optimum.honored, [true, false, false, true, true]);
+    assert_eq!(optimum.penalty, 3);
+    assert_eq!(validate::violations(&optimum.solution).unwrap(), []);
+    assert!(optimum.solution[0].am);
+    assert_eq!(optimum.solution[1].room, 1);
+}
+
+#[test]
+fn test_meeting2_reschedule() {
+    let previous = schedule::parse_solutions("PM R1, AM R1, AM R2, PM R2").unwrap();
+    let required = ["M2 !AM".parse().unwrap()];
+
+    // M2 leaves the morning, which drags M1 along (rule 1), nothing else needs to move
+    let optimum = optimize_with(&Goals {
+        previous: &previous,
+        required: &required,
+        ..Goals::default()
+    })
+    .unwrap();
+
+    assert_eq!(optimum.changed, [0, 1]);
+    assert_eq!(validate::violations(&optimum.solution).unwrap(), []);
+    assert!(!optimum.solution[1].am);
+    assert_eq!(optimum.solution[2..], previous[2..]);
 }
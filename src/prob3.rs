@@ -1,7 +1,13 @@
 mod types;
 
 use types::types_prob3::{
-    Beverage::*, Cigar::*, Color::*, House, Nationality::*, Pet::*, Solution, ToZ3Int,
+    Beverage::{self, *},
+    Cigar::{self, *},
+    Color::{self, *},
+    House,
+    Nationality::{self, *},
+    Pet::{self, *},
+    Solution,
 };
 use z3::{
     ast::{self, Ast},
@@ -49,29 +55,35 @@ fn solve() -> Option<Vec<Solution>> {
     // We solve by house, which are gather in a street (I know, don't judge me)
     let street: [House; 5] = array_init::array_init(|i: usize| House::new(&ctx, &solver, i as i8));
 
+    // Each characteristic is unique (one fish, one milk, etc)
+    let indexes: Vec<&ast::Int> = street.iter().map(|house| &house.index).collect();
+    let colors: Vec<&ast::Int> = street.iter().map(|house| &house.color).collect();
+    let nationalities: Vec<&ast::Int> = street.iter().map(|house| &house.nationality).collect();
+    let beverages: Vec<&ast::Int> = street.iter().map(|house| &house.beverage).collect();
+    let cigars: Vec<&ast::Int> = street.iter().map(|house| &house.cigar).collect();
+    let pets: Vec<&ast::Int> = street.iter().map(|house| &house.pet).collect();
+    solver.assert(&ast::Int::distinct(&ctx, &indexes));
+    solver.assert(&Color::z3_distinct(&ctx, &colors));
+    solver.assert(&Nationality::z3_distinct(&ctx, &nationalities));
+    solver.assert(&Beverage::z3_distinct(&ctx, &beverages));
+    solver.assert(&Cigar::z3_distinct(&ctx, &cigars));
+    solver.assert(&Pet::z3_distinct(&ctx, &pets));
+
     // Main loop, apply constraints on each house
     for (i, house) in street.iter().enumerate() {
-        // Each characteristic is unique (one fish, one milk, etc)
-        // Skippy made me do the skip:
-        for other_house in street.iter().skip(i + 1) {
-            let fields_of_i = house.serialize();
-            let fields_of_j = other_house.serialize();
-
-            for (field_i, field_j) in fields_of_i.iter().zip(fields_of_j.iter()) {
-                solver.assert(&!(&field_i._eq(field_j)));
-            }
-        }
-
-        // Each entries is bounded [0, 4]
-        house.serialize().iter().for_each(|x| {
-            solver.assert(&x.ge(&ast::Int::from_u64(&ctx, 0)));
-            solver.assert(&x.lt(&ast::Int::from_u64(&ctx, 5)));
-        });
+        // Each entry is one of its enum, the index one of the 5 houses
+        solver.assert(&house.index.ge(&ast::Int::from_u64(&ctx, 0)));
+        solver.assert(&house.index.lt(&ast::Int::from_u64(&ctx, 5)));
+        solver.assert(&Color::z3_bounds(&house.color));
+        solver.assert(&Nationality::z3_bounds(&house.nationality));
+        solver.assert(&Beverage::z3_bounds(&house.beverage));
+        solver.assert(&Cigar::z3_bounds(&house.cigar));
+        solver.assert(&Pet::z3_bounds(&house.pet));
 
         // brit in red house
         house.constrain_implication(
             &house.nationality._eq(&Brit.to_z3_int(&ctx)),
-            &house.color._eq(&ast::Int::from_i64(&ctx, Red as i64)),
+            &house.color._eq(&Red.to_z3_int(&ctx)),
         );
 
         // Swede keeps dogs
@@ -94,15 +106,11 @@ fn solve() -> Option<Vec<Solution>> {
                 continue;
             }
 
-            solver.assert(
-                &(&house.color._eq(&Green.to_z3_int(&ctx))
-                    & &other_house.color._eq(&White.to_z3_int(&ctx)))
-                    .implies(
-                        &house
-                            .index
-                            ._eq(&(&other_house.index - &ast::Int::from_i64(&ctx, 1))),
-                    ),
-            )
+            house.constrain_left_of(
+                other_house,
+                &house.color._eq(&Green.to_z3_int(&ctx)),
+                &other_house.color._eq(&White.to_z3_int(&ctx)),
+            );
         }
 
         // Green coffee
@@ -234,17 +242,7 @@ fn solve() -> Option<Vec<Solution>> {
             .map(|house| {
                 (
                     model.eval(&house.index, true).unwrap().as_i64().unwrap(),
-                    Solution {
-                        color: model.eval(&house.color, true).unwrap().as_i64().unwrap(),
-                        nationality: model
-                            .eval(&house.nationality, true)
-                            .unwrap()
-                            .as_i64()
-                            .unwrap(),
-                        beverage: model.eval(&house.beverage, true).unwrap().as_i64().unwrap(),
-                        cigar: model.eval(&house.cigar, true).unwrap().as_i64().unwrap(),
-                        pet: model.eval(&house.pet, true).unwrap().as_i64().unwrap(),
-                    },
+                    Solution::decode(&model, house).expect("z3 constrained this"),
                 )
             })
             .collect();
@@ -273,39 +271,39 @@ fn test_einstein() {
 
     let solution = [
         Solution {
-            color: Yellow as i64,
-            nationality: Norwegian as i64,
-            beverage: Water as i64,
-            cigar: Dunhill as i64,
-            pet: Cats as i64,
+            color: Yellow,
+            nationality: Norwegian,
+            beverage: Water,
+            cigar: Dunhill,
+            pet: Cats,
         },
         Solution {
-            color: Blue as i64,
-            nationality: Dane as i64,
-            beverage: Tea as i64,
-            cigar: Blend as i64,
-            pet: Horses as i64,
+            color: Blue,
+            nationality: Dane,
+            beverage: Tea,
+            cigar: Blend,
+            pet: Horses,
         },
         Solution {
-            color: Red as i64,
-            nationality: Brit as i64,
-            beverage: Milk as i64,
-            cigar: PallMall as i64,
-            pet: Birds as i64,
+            color: Red,
+            nationality: Brit,
+            beverage: Milk,
+            cigar: PallMall,
+            pet: Birds,
         },
         Solution {
-            color: Green as i64,
-            nationality: German as i64,
-            beverage: Coffee as i64,
-            cigar: Prince as i64,
-            pet: Fish as i64,
+            color: Green,
+            nationality: German,
+            beverage: Coffee,
+            cigar: Prince,
+            pet: Fish,
         },
         Solution {
-            color: White as i64,
-            nationality: Swede as i64,
-            beverage: Beer as i64,
-            cigar: BlueMaster as i64,
-            pet: Dogs as i64,
+            color: White,
+            nationality: Swede,
+            beverage: Beer,
+            cigar: BlueMaster,
+            pet: Dogs,
         },
     ];
 
@@ -1,12 +1,8 @@
-mod types;
-
 use z3::{
     ast::{self, Ast, BV},
     Config, Context, SatResult, Solver,
 };
 
-struct Solution {}
-
 fn main() {
     println!(
         "
@@ -0,0 +1,491 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use z3::ast::Bool;
+
+use super::{
+    definition::Definition,
+    street::{House, Solution, Street},
+};
+
+/// A value of some category, by index into the definition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Value {
+    pub category: usize,
+    pub value: usize,
+}
+
+/// One clue, see `Clue::parse` for the syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Clue {
+    /// Both values are in the same house
+    Same(Value, Value),
+    /// The first value's house is immediately left of the second's
+    LeftOf(Value, Value),
+    /// The first value's house is immediately right of the second's
+    RightOf(Value, Value),
+    /// The first value's house is anywhere left of the second's
+    SomewhereLeftOf(Value, Value),
+    /// The first value's house is anywhere right of the second's
+    SomewhereRightOf(Value, Value),
+    /// The houses of the two values are neighbors
+    NextTo(Value, Value),
+    /// The houses of the two values are exactly that many houses apart
+    Apart(Value, Value, usize),
+    /// The value is in the house at that position, 0 being the first one
+    At(Value, usize),
+    /// The value is in the first or the last house
+    AtEnd(Value),
+    /// The first value's house is somewhere between the houses of the other two
+    Between(Value, Value, Value),
+    /// The clue does not hold
+    Not(Box<Clue>),
+}
+
+/// Builds a clue relating two values
+type Relation = fn(Value, Value) -> Clue;
+
+/// Relations between two values, as written in clues
+const RELATIONS: [(&str, Relation); 6] = [
+    (" = ", Clue::Same),
+    (" left-of ", Clue::LeftOf),
+    (" right-of ", Clue::RightOf),
+    (" somewhere-left-of ", Clue::SomewhereLeftOf),
+    (" somewhere-right-of ", Clue::SomewhereRightOf),
+    (" next-to ", Clue::NextTo),
+];
+
+impl Clue {
+    /// `Brit = Red`, `Green left-of White`, `Blend next-to Cats`, `Milk at 3` (houses from 1),
+    /// `right-of`, `somewhere-left-of`, `somewhere-right-of`, `Dane 2-apart Tea`, `Fish at-end`,
+    /// `Milk between Red and Dogs`, and `not ` in front of any of them.
+    /// Values are looked up in the definition.
+    pub fn parse(s: &str, definition: &Definition) -> anyhow::Result<Self> {
+        let value = |name: &str| {
+            definition
+                .find(name)
+                .map(|(category, value)| Value { category, value })
+                .ok_or_else(|| anyhow!("unknown value `{}`", name.trim()))
+        };
+        let s = s.trim();
+
+        if let Some(clue) = s.strip_prefix("not ") {
+            return Ok(Clue::Not(Box::new(Clue::parse(clue, definition)?)));
+        }
+
+        if let Some((a, others)) = s.split_once(" between ") {
+            let (b, c) = others
+                .split_once(" and ")
+                .ok_or_else(|| anyhow!("expected `A between B and C`, got `{}`", s))?;
+            return Ok(Clue::Between(value(a)?, value(b)?, value(c)?));
+        }
+
+        for (keyword, clue) in RELATIONS {
+            if let Some((a, b)) = s.split_once(keyword) {
+                return Ok(clue(value(a)?, value(b)?));
+            }
+        }
+
+        if let Some((a_distance, b)) = s.split_once("-apart ") {
+            let (a, distance) = a_distance
+                .rsplit_once(' ')
+                .ok_or_else(|| anyhow!("expected `A N-apart B`, got `{}`", s))?;
+            let distance: usize = distance
+                .parse()
+                .ok()
+                .filter(|&d| d >= 1 && d < definition.houses)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "expected a distance from 1 to {}, got `{}`",
+                        definition.houses - 1,
+                        distance
+                    )
+                })?;
+            return Ok(Clue::Apart(value(a)?, value(b)?, distance));
+        }
+
+        if let Some(a) = s.strip_suffix(" at-end") {
+            return Ok(Clue::AtEnd(value(a)?));
+        }
+
+        if let Some((a, position)) = s.split_once(" at ") {
+            let position: usize = position
+                .trim()
+                .parse()
+                .ok()
+                .filter(|&p| p >= 1 && p <= definition.houses)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "expected a house from 1 to {}, got `{}`",
+                        definition.houses,
+                        position.trim()
+                    )
+                })?;
+            return Ok(Clue::At(value(a)?, position - 1));
+        }
+
+        bail!(
+            "expected `A = B`, `A left-of B`, `A next-to B`, `A at N` or another relation, got `{}`",
+            s
+        )
+    }
+
+    /// Back to the clue syntax
+    pub fn text(&self, definition: &Definition) -> String {
+        let name = |v: &Value| definition.value(v.category, v.value);
+
+        match self {
+            Clue::Same(a, b) => format!("{} = {}", name(a), name(b)),
+            Clue::LeftOf(a, b) => format!("{} left-of {}", name(a), name(b)),
+            Clue::RightOf(a, b) => format!("{} right-of {}", name(a), name(b)),
+            Clue::SomewhereLeftOf(a, b) => format!("{} somewhere-left-of {}", name(a), name(b)),
+            Clue::SomewhereRightOf(a, b) => format!("{} somewhere-right-of {}", name(a), name(b)),
+            Clue::NextTo(a, b) => format!("{} next-to {}", name(a), name(b)),
+            Clue::Apart(a, b, distance) => format!("{} {}-apart {}", name(a), distance, name(b)),
+            Clue::At(a, position) => format!("{} at {}", name(a), position + 1),
+            Clue::AtEnd(a) => format!("{} at-end", name(a)),
+            Clue::Between(a, b, c) => format!("{} between {} and {}", name(a), name(b), name(c)),
+            Clue::Not(clue) => format!("not {}", clue.text(definition)),
+        }
+    }
+
+    /// Whether the clue holds on a solved grid, without z3
+    pub fn holds(&self, solution: &Solution) -> bool {
+        let position = |v: &Value| solution.position(v.category, v.value);
+
+        match self {
+            Clue::Same(a, b) => position(a) == position(b),
+            Clue::LeftOf(a, b) => position(a) + 1 == position(b),
+            Clue::RightOf(a, b) => position(a) == position(b) + 1,
+            Clue::SomewhereLeftOf(a, b) => position(a) < position(b),
+            Clue::SomewhereRightOf(a, b) => position(a) > position(b),
+            Clue::NextTo(a, b) => position(a).abs_diff(position(b)) == 1,
+            Clue::Apart(a, b, distance) => position(a).abs_diff(position(b)) == *distance,
+            Clue::At(a, at) => position(a) == *at,
+            Clue::AtEnd(a) => position(a) == 0 || position(a) == solution.houses.len() - 1,
+            Clue::Between(a, b, c) => {
+                let (a, b, c) = (position(a), position(b), position(c));
+                (b < a && a < c) || (c < a && a < b)
+            }
+            Clue::Not(clue) => !clue.holds(solution),
+        }
+    }
+
+    pub fn constraints<'ctx>(&self, street: &Street<'ctx>) -> Vec<Bool<'ctx>> {
+        self.relate(street, false)
+    }
+
+    /// Whichever houses hold the values, they stand in the relation (or not, when `negated`).
+    /// Pairs include a house with itself, so "left of" also keeps two values out of the same house.
+    fn relate<'ctx>(&self, street: &Street<'ctx>, negated: bool) -> Vec<Bool<'ctx>> {
+        let holds = |relation: Bool<'ctx>| if negated { relation.not() } else { relation };
+        let houses = street.houses.len();
+
+        match self {
+            Clue::Same(a, b) => street
+                .houses
+                .iter()
+                .map(|house| {
+                    house
+                        .is(a.category, a.value)
+                        .implies(&holds(house.is(b.category, b.value)))
+                })
+                .collect(),
+            Clue::LeftOf(a, b) => pairs(street, *a, *b, |house, other| holds(house.left_of(other))),
+            Clue::RightOf(a, b) => {
+                pairs(street, *a, *b, |house, other| holds(house.right_of(other)))
+            }
+            Clue::SomewhereLeftOf(a, b) => pairs(street, *a, *b, |house, other| {
+                holds(house.somewhere_left_of(other))
+            }),
+            Clue::SomewhereRightOf(a, b) => pairs(street, *a, *b, |house, other| {
+                holds(house.somewhere_right_of(other))
+            }),
+            Clue::NextTo(a, b) => pairs(street, *a, *b, |house, other| holds(house.next_to(other))),
+            Clue::Apart(a, b, distance) => pairs(street, *a, *b, |house, other| {
+                holds(house.apart(other, *distance))
+            }),
+            Clue::At(a, position) => street
+                .houses
+                .iter()
+                .map(|house| {
+                    house
+                        .is(a.category, a.value)
+                        .implies(&holds(house.at(*position)))
+                })
+                .collect(),
+            Clue::AtEnd(a) => street
+                .houses
+                .iter()
+                .map(|house| {
+                    house
+                        .is(a.category, a.value)
+                        .implies(&holds(house.at_end(houses)))
+                })
+                .collect(),
+            Clue::Between(a, b, c) => {
+                let mut constraints = Vec::new();
+                for house in &street.houses {
+                    for one in &street.houses {
+                        for other in &street.houses {
+                            let placed = house.is(a.category, a.value)
+                                & one.is(b.category, b.value)
+                                & other.is(c.category, c.value);
+                            constraints.push(placed.implies(&holds(house.between(one, other))));
+                        }
+                    }
+                }
+                constraints
+            }
+            Clue::Not(clue) => clue.relate(street, !negated),
+        }
+    }
+}
+
+/// For every two houses (the same one twice included), `a` in the first and `b` in the
+/// second means `relation` between them
+fn pairs<'ctx>(
+    street: &Street<'ctx>,
+    a: Value,
+    b: Value,
+    relation: impl Fn(&House<'ctx>, &House<'ctx>) -> Bool<'ctx>,
+) -> Vec<Bool<'ctx>> {
+    let mut constraints = Vec::new();
+
+    for house in &street.houses {
+        for other in &street.houses {
+            constraints.push(
+                (house.is(a.category, a.value) & other.is(b.category, b.value))
+                    .implies(&relation(house, other)),
+            );
+        }
+    }
+
+    constraints
+}
+
+/// A definition followed by a `clues:` line and one clue per line
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    pub definition: Definition,
+    pub clues: Vec<Clue>,
+}
+
+impl FromStr for Puzzle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let lines: Vec<&str> = s.lines().collect();
+        let start = lines
+            .iter()
+            .position(|line| line.trim() == "clues:")
+            .unwrap_or(lines.len());
+
+        let definition: Definition = s.parse()?;
+
+        let mut clues = Vec::new();
+        for (number, line) in lines.iter().enumerate().skip(start + 1) {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            clues.push(
+                Clue::parse(line, &definition).with_context(|| format!("line {}", number + 1))?,
+            );
+        }
+
+        Ok(Puzzle { definition, clues })
+    }
+}
+
+impl Puzzle {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path))?
+            .parse()
+            .with_context(|| format!("parsing {}", path))
+    }
+
+    /// The puzzle in its file format
+    pub fn text(&self) -> String {
+        let mut text = self.definition.to_string();
+        text.push_str("clues:\n");
+        for clue in &self.clues {
+            text.push_str(&clue.text(&self.definition));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_clues() {
+    use super::definition::EINSTEIN;
+
+    let puzzle: Puzzle = EINSTEIN.parse().unwrap();
+    let definition = &puzzle.definition;
+    assert_eq!(puzzle.clues.len(), 15);
+
+    let brit = Value {
+        category: 1,
+        value: 0,
+    };
+    let red = Value {
+        category: 0,
+        value: 0,
+    };
+    assert_eq!(
+        Clue::parse("Brit = Red", definition).unwrap(),
+        Clue::Same(brit, red)
+    );
+    assert_eq!(
+        Clue::parse("brit at 5", definition).unwrap(),
+        Clue::At(brit, 4)
+    );
+    assert_eq!(puzzle.clues[9].text(definition), "Blend next-to Cats");
+    assert_eq!(puzzle.text().parse::<Puzzle>().unwrap().clues, puzzle.clues);
+
+    let error = Clue::parse("Brit = Purple", definition).unwrap_err();
+    assert_eq!(error.to_string(), "unknown value `Purple`");
+    assert!(Clue::parse("Milk at 6", definition).is_err());
+    assert!(Clue::parse("Milk near Cats", definition).is_err());
+    assert!(Clue::parse("Milk 5-apart Cats", definition).is_err());
+    assert!(Clue::parse("Milk between Cats", definition).is_err());
+
+    for text in [
+        "not Brit = Red",
+        "Tea right-of Dogs",
+        "Fish somewhere-left-of Dogs",
+        "not not Dane somewhere-right-of Prince",
+        "Dane 2-apart Tea",
+        "Fish at-end",
+        "Milk between Red and Dogs",
+        "not Beer at 2",
+    ] {
+        let clue = Clue::parse(text, definition).unwrap();
+        assert_eq!(clue.text(definition), text);
+    }
+    let error = format!(
+        "{:#}",
+        format!("{}clues:\nZebra = Red", definition)
+            .parse::<Puzzle>()
+            .unwrap_err()
+    );
+    assert!(
+        error.contains("line 8") && error.contains("Zebra"),
+        "{}",
+        error
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_relations() {
+    use super::street::Encoding;
+    use z3::{Config, Context, SatResult, Solver};
+
+    // Every grid of a street: each category is a permutation of the houses
+    fn grids(houses: usize, categories: usize) -> Vec<Solution> {
+        fn permutations(items: Vec<usize>) -> Vec<Vec<usize>> {
+            if items.is_empty() {
+                return vec![Vec::new()];
+            }
+            let mut all = Vec::new();
+            for (i, &first) in items.iter().enumerate() {
+                let mut rest = items.clone();
+                rest.remove(i);
+                for mut permutation in permutations(rest) {
+                    permutation.insert(0, first);
+                    all.push(permutation);
+                }
+            }
+            all
+        }
+
+        let mut columns = vec![Vec::new()];
+        for _ in 0..categories {
+            columns = columns
+                .iter()
+                .flat_map(|previous: &Vec<Vec<usize>>| {
+                    permutations((0..houses).collect()).into_iter().map(|p| {
+                        let mut next = previous.clone();
+                        next.push(p);
+                        next
+                    })
+                })
+                .collect();
+        }
+        columns
+            .into_iter()
+            .map(|columns| Solution {
+                houses: (0..houses)
+                    .map(|h| columns.iter().map(|column| column[h]).collect())
+                    .collect(),
+            })
+            .collect()
+    }
+
+    let streets = [
+        "color: red, blue, green\npet: cat, dog, fish",
+        "color: red, blue, green, white",
+    ];
+    let clues = [
+        "red = cat",
+        "red left-of cat",
+        "red right-of blue",
+        "blue somewhere-left-of fish",
+        "red somewhere-right-of green",
+        "green next-to dog",
+        "red 2-apart blue",
+        "red 2-apart fish",
+        "blue at 2",
+        "green at-end",
+        "red between blue and green",
+        "red between cat and fish",
+        "white between red and blue",
+        "red 3-apart white",
+        "white next-to red",
+    ];
+
+    for street in streets {
+        let definition: Definition = street.parse().unwrap();
+        let grids = grids(definition.houses, definition.categories.len());
+
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let solver = Solver::new(&ctx);
+        let street = Street::new(&ctx, &definition, Encoding::INT);
+        for constraint in street.constraints() {
+            solver.assert(&constraint);
+        }
+
+        let parsed = clues
+            .iter()
+            .filter_map(|text| Clue::parse(text, &definition).ok());
+        let negated = parsed.clone().map(|clue| Clue::Not(Box::new(clue)));
+        for clue in parsed.chain(negated) {
+            solver.push();
+            for constraint in clue.constraints(&street) {
+                solver.assert(&constraint);
+            }
+
+            // z3 allows exactly the grids the plain evaluation accepts
+            for grid in &grids {
+                solver.push();
+                solver.assert(&street.matches(grid));
+                let sat = solver.check() == SatResult::Sat;
+                solver.pop(1);
+
+                assert_eq!(
+                    sat,
+                    clue.holds(grid),
+                    "`{}` on {:?}",
+                    clue.text(&definition),
+                    grid
+                );
+            }
+            solver.pop(1);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::{fmt, fs, str::FromStr};
+
+use anyhow::{anyhow, bail, Context};
+
+/// One attribute of the houses (color, pet...) and the values it takes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Category {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// The street of a logic grid puzzle: how many houses, and the categories of the
+/// houses, each one taking every one of its values exactly once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub houses: usize,
+    pub categories: Vec<Category>,
+}
+
+/// One `<category>: <value>, <value>, ...` line per category, all with as many values as
+/// there are houses. An optional `houses: N` line checks that count. `#` starts a comment,
+/// and the definition ends at a `clues:` line if any.
+impl FromStr for Definition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut houses = None;
+        let mut categories: Vec<Category> = Vec::new();
+
+        for (number, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "clues:" {
+                break;
+            }
+
+            let (name, values) = line.split_once(':').ok_or_else(|| {
+                anyhow!(
+                    "line {}: expected `<category>: <value>, ...`, got `{}`",
+                    number + 1,
+                    line
+                )
+            })?;
+            let name = name.trim();
+
+            if name == "houses" {
+                houses = Some(
+                    values
+                        .trim()
+                        .parse()
+                        .map_err(|_| anyhow!("line {}: bad number of houses", number + 1))?,
+                );
+                continue;
+            }
+
+            let values: Vec<String> = values
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect();
+            categories.push(Category {
+                name: name.to_string(),
+                values,
+            });
+        }
+
+        let definition = Definition {
+            houses: houses
+                .or_else(|| categories.first().map(|c| c.values.len()))
+                .ok_or_else(|| anyhow!("no category in the definition"))?,
+            categories,
+        };
+        definition.check()?;
+        Ok(definition)
+    }
+}
+
+impl fmt::Display for Definition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "houses: {}", self.houses)?;
+        for category in &self.categories {
+            writeln!(f, "{}: {}", category.name, category.values.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Definition {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path))?
+            .parse()
+            .with_context(|| format!("parsing {}", path))
+    }
+
+    /// Every category fills the street, value names are unique across categories
+    /// (clues refer to values without their category)
+    fn check(&self) -> anyhow::Result<()> {
+        if self.houses == 0 {
+            bail!("a street needs at least one house");
+        }
+
+        let mut seen: Vec<(&str, &str)> = Vec::new();
+        for category in &self.categories {
+            if category.values.len() != self.houses {
+                bail!(
+                    "`{}` has {} values for {} houses",
+                    category.name,
+                    category.values.len(),
+                    self.houses
+                );
+            }
+            if self
+                .categories
+                .iter()
+                .filter(|c| c.name == category.name)
+                .count()
+                > 1
+            {
+                bail!("category `{}` is declared twice", category.name);
+            }
+            for value in &category.values {
+                if let Some((_, other)) = seen.iter().find(|(v, _)| v.eq_ignore_ascii_case(value)) {
+                    bail!("`{}` is both a {} and a {}", value, other, category.name);
+                }
+                seen.push((value, &category.name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// (category, value) indices of a value name, ignoring case
+    pub fn find(&self, value: &str) -> Option<(usize, usize)> {
+        self.categories
+            .iter()
+            .enumerate()
+            .find_map(|(c, category)| {
+                category
+                    .values
+                    .iter()
+                    .position(|v| v.eq_ignore_ascii_case(value.trim()))
+                    .map(|v| (c, v))
+            })
+    }
+
+    pub fn value(&self, category: usize, value: usize) -> &str {
+        &self.categories[category].values[value]
+    }
+}
+
+/// The classic five house puzzle, see the readme
+pub const EINSTEIN: &str = include_str!("../../puzzles/einstein.txt");
+
+#[cfg(test)]
+#[test]
+fn test_definition() {
+    let einstein: Definition = EINSTEIN.parse().unwrap();
+    assert_eq!(einstein.houses, 5);
+    assert_eq!(einstein.categories.len(), 5);
+    assert_eq!(einstein.categories[3].values[0], "Pall Mall");
+    assert_eq!(einstein.find("pall mall"), Some((3, 0)));
+    assert_eq!(einstein.find("Horses"), Some((4, 4)));
+    assert_eq!(einstein.find("Zebra"), None);
+    assert_eq!(
+        einstein.to_string().parse::<Definition>().unwrap(),
+        einstein
+    );
+
+    let small: Definition = "houses: 3\ncolor: red, blue, green\npet: cat, dog, fish"
+        .parse()
+        .unwrap();
+    assert_eq!(small.houses, 3);
+
+    assert!("houses: 3\ncolor: red, blue".parse::<Definition>().is_err());
+    assert!("color: red, blue\npet: cat, dog, fish"
+        .parse::<Definition>()
+        .is_err());
+    assert!("color: red, blue\nflag: red, white"
+        .parse::<Definition>()
+        .is_err());
+    assert!("just some words".parse::<Definition>().is_err());
+}
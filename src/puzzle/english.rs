@@ -0,0 +1,250 @@
+use anyhow::bail;
+
+use super::{
+    clues::{Clue, Value},
+    definition::Definition,
+};
+
+/// Words saying that two values share a house: "The Brit lives in the red house",
+/// "The person who smokes Pall Mall rears birds". Not a bare "is" or "has", which show up in
+/// sentences about anything ("The Brit is two houses away from the Dane").
+const SAME_HOUSE: [&str; 9] = [
+    "lives in",
+    "living in",
+    "is in",
+    "keeps",
+    "drinks",
+    "smokes",
+    "rears",
+    "owns",
+    "owner",
+];
+
+/// Words in front of "left of" / "right of" meaning the very next house
+const IMMEDIATE: [&str; 4] = ["immediate", "immediately", "directly", "just"];
+
+/// Words for a neighbor, either side
+const NEIGHBOR: [&str; 4] = ["next to", "neighbor", "neighbour", "beside"];
+
+const NEGATIONS: [&str; 4] = ["not", "doesn", "never", "no"];
+
+/// Words for a distance this parser can't read, the sentence is not a same house or neighbor clue
+const DISTANCES: [&str; 6] = ["away", "far", "from", "apart", "between", "houses"];
+
+const ORDINALS: [&str; 10] = [
+    "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth", "tenth",
+];
+
+/// Sentences read from a text, the clues understood and the sentences that were not
+#[derive(Debug)]
+pub struct Reading {
+    pub clues: Vec<Clue>,
+    pub unknown: Vec<(String, anyhow::Error)>,
+}
+
+/// Every sentence of `text`, split on line ends and full stops. List bullets, quotes and
+/// `#` comments are ignored.
+pub fn read(text: &str, definition: &Definition) -> Reading {
+    let mut reading = Reading {
+        clues: Vec::new(),
+        unknown: Vec::new(),
+    };
+
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap();
+        for sentence in line.split('.') {
+            let sentence = sentence
+                .trim()
+                .trim_start_matches(['-', '*'])
+                .trim_matches(|c: char| c.is_whitespace() || c == '"');
+            if sentence.is_empty() {
+                continue;
+            }
+            match parse(sentence, definition) {
+                Ok(clue) => reading.clues.push(clue),
+                Err(err) => reading.unknown.push((sentence.to_string(), err)),
+            }
+        }
+    }
+
+    reading
+}
+
+/// One sentence in the usual zebra puzzle phrasing: the values it names (from the definition,
+/// singular or plural) and how they relate, "next to", "on the immediate left of", "lives in the
+/// first house"... Two values joined by a verb like "lives in" or "keeps" share a house.
+pub fn parse(sentence: &str, definition: &Definition) -> anyhow::Result<Clue> {
+    let words = words(sentence);
+    let text = format!(" {} ", words.join(" "));
+    let has = |phrase: &str| text.contains(&format!(" {} ", phrase));
+
+    if NEGATIONS.iter().any(|word| has(word)) {
+        bail!("negated clues are not supported");
+    }
+    if DISTANCES.iter().any(|word| has(word)) {
+        bail!("distances between houses are not supported");
+    }
+
+    let values = values(&words, definition);
+    match values[..] {
+        [] => bail!("no value of the puzzle in this sentence"),
+        [value] => {
+            let position = ORDINALS
+                .iter()
+                .take(definition.houses)
+                .position(|ordinal| has(ordinal))
+                .or_else(|| has("last").then(|| definition.houses - 1));
+            if let Some(position) = position {
+                return Ok(Clue::At(value, position));
+            }
+            if has("center") || has("centre") || has("middle") {
+                if definition.houses.is_multiple_of(2) {
+                    bail!("no center house in a street of {}", definition.houses);
+                }
+                return Ok(Clue::At(value, definition.houses / 2));
+            }
+            bail!("only one value and no house position")
+        }
+        [a, b] => {
+            let immediate = IMMEDIATE.iter().any(|word| has(word));
+            if has("left") || has("right") {
+                if !immediate {
+                    bail!("left or right of, but not said to be immediately");
+                }
+                return Ok(if has("left") {
+                    Clue::LeftOf(a, b)
+                } else {
+                    Clue::LeftOf(b, a)
+                });
+            }
+            if NEIGHBOR.iter().any(|word| has(word)) {
+                return Ok(Clue::NextTo(a, b));
+            }
+            if SAME_HOUSE.iter().any(|word| has(word)) {
+                if a.category == b.category {
+                    bail!(
+                        "two {} values can't share a house",
+                        definition.categories[a.category].name
+                    );
+                }
+                return Ok(Clue::Same(a, b));
+            }
+            bail!("no relation between the two values")
+        }
+        _ => bail!("more than two values in one sentence"),
+    }
+}
+
+/// Lower case words, anything but letters and digits separates them ("house’s" is "house s")
+fn words(sentence: &str) -> Vec<String> {
+    sentence
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The values named in `sentence` (its words), in the order they first appear
+fn values(sentence: &[String], definition: &Definition) -> Vec<Value> {
+    let mut names: Vec<(Vec<String>, Value)> = Vec::new();
+    for (category, c) in definition.categories.iter().enumerate() {
+        for (value, name) in c.values.iter().enumerate() {
+            names.push((words(name), Value { category, value }));
+        }
+    }
+    // "Pall Mall" before a value named "Mall"
+    names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    let mut found: Vec<Value> = Vec::new();
+    let mut i = 0;
+    while i < sentence.len() {
+        let matched = names.iter().find(|(name, _)| {
+            sentence.len() - i >= name.len()
+                && name
+                    .iter()
+                    .zip(&sentence[i..])
+                    .all(|(expected, word)| same_word(expected, word))
+        });
+        match matched {
+            Some((name, value)) => {
+                if !found.contains(value) {
+                    found.push(*value);
+                }
+                i += name.len();
+            }
+            None => i += 1,
+        }
+    }
+    found
+}
+
+/// Equal, or one is the plural of the other ("blends" for Blend, "dog" for Dogs)
+fn same_word(expected: &str, word: &str) -> bool {
+    expected == word
+        || word.strip_suffix('s') == Some(expected)
+        || expected.strip_suffix('s') == Some(word)
+}
+
+#[cfg(test)]
+#[test]
+fn test_english() {
+    use super::{clues::Puzzle, definition::EINSTEIN};
+
+    // The clues as the readme words them, in the order of puzzles/einstein.txt
+    let text = include_str!("../../puzzles/einstein-english.txt");
+
+    let puzzle: Puzzle = EINSTEIN.parse().unwrap();
+    let definition = &puzzle.definition;
+    let reading = read(text, definition);
+    assert!(reading.unknown.is_empty(), "{:?}", reading.unknown);
+    assert_eq!(reading.clues, puzzle.clues);
+
+    let clue = parse(
+        "The white house is immediately right of the green one",
+        definition,
+    );
+    assert_eq!(clue.unwrap(), puzzle.clues[3]);
+    assert_eq!(
+        parse("The Dane lives in the last house", definition).unwrap(),
+        Clue::At(
+            Value {
+                category: 1,
+                value: 2
+            },
+            4
+        )
+    );
+
+    let reading = read(
+        "The Swede keeps dogs. The Brit is not in the red house. Zebras are nice.\n\
+         The Dane lives left of the Swede. Tea, coffee and milk are drinks.\n\
+         The Brit is two houses away from the Dane. The Brit has a house far from the red house.\n\
+         The Brit lives in the Dane's house. The Brit is in the red house",
+        definition,
+    );
+    assert_eq!(
+        reading.clues,
+        [puzzle.clues[1].clone(), puzzle.clues[0].clone()]
+    );
+    let unknown: Vec<&str> = reading.unknown.iter().map(|(s, _)| s.as_str()).collect();
+    assert_eq!(
+        unknown,
+        [
+            "The Brit is not in the red house",
+            "Zebras are nice",
+            "The Dane lives left of the Swede",
+            "Tea, coffee and milk are drinks",
+            "The Brit is two houses away from the Dane",
+            "The Brit has a house far from the red house",
+            "The Brit lives in the Dane's house"
+        ]
+    );
+    assert_eq!(
+        reading.unknown[1].1.to_string(),
+        "no value of the puzzle in this sentence"
+    );
+    assert_eq!(
+        reading.unknown[6].1.to_string(),
+        "two nationality values can't share a house"
+    );
+}
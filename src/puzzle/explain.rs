@@ -0,0 +1,137 @@
+use anyhow::bail;
+use z3::{Config, Context};
+
+use super::{
+    clues::{Clue, Puzzle, Value},
+    definition::Definition,
+    street::Encoding,
+    unique::Checker,
+};
+
+/// One value placed, and the clues of the puzzle it follows from (clue numbers from 0),
+/// including those behind the earlier steps it uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub value: Value,
+    pub house: usize,
+    pub clues: Vec<usize>,
+}
+
+impl Step {
+    /// "House 2 is Blue (clues 9, 14)", houses and clues numbered from 1
+    pub fn text(&self, definition: &Definition) -> String {
+        let clues: Vec<String> = self.clues.iter().map(|c| (c + 1).to_string()).collect();
+        let cited = match clues.len() {
+            0 => "the street alone".to_string(),
+            1 => format!("clue {}", clues[0]),
+            _ => format!("clues {}", clues.join(", ")),
+        };
+        format!(
+            "House {} is {} ({})",
+            self.house + 1,
+            definition.value(self.value.category, self.value.value),
+            cited
+        )
+    }
+}
+
+/// Every value of the solution placed in turn, the way it could be worked out by hand: each step
+/// is a value whose house the clues and the steps before it force, the one citing the fewest
+/// clues. Fails unless the puzzle has exactly one solution.
+pub fn explain(puzzle: &Puzzle) -> anyhow::Result<Vec<Step>> {
+    // Hundreds of small checks, one-hot is the fastest encoding for them
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let mut checker = Checker::new(&ctx, puzzle, Encoding::OneHot);
+
+    let all: Vec<usize> = (0..puzzle.clues.len()).collect();
+    let Some(solution) = checker.solve(&all, None) else {
+        bail!("the clues have no solution");
+    };
+    if !checker.unique(&all, &solution) {
+        bail!("the clues have more than one solution");
+    }
+
+    // The clues, then each step as a clue of its own, and the puzzle clues behind each of them
+    let mut premises = all.clone();
+    let mut behind: Vec<Vec<usize>> = all.iter().map(|&c| vec![c]).collect();
+
+    let definition = &puzzle.definition;
+    let mut unknown: Vec<Value> = (0..definition.categories.len())
+        .flat_map(|category| (0..definition.houses).map(move |value| Value { category, value }))
+        .collect();
+    let mut steps: Vec<Step> = Vec::new();
+
+    while !unknown.is_empty() {
+        let mut best: Option<(usize, Vec<usize>, Vec<usize>)> = None;
+        for (i, value) in unknown.iter().enumerate() {
+            let fact = Clue::At(*value, solution.position(value.category, value.value));
+            let used = checker
+                .implies(&premises, &fact)
+                .expect("a single solution forces every value");
+
+            let mut clues: Vec<usize> = used.iter().flat_map(|&p| behind[p].clone()).collect();
+            clues.sort();
+            clues.dedup();
+            let simpler = match &best {
+                Some((_, best_used, best_clues)) => {
+                    (clues.len(), used.len()) < (best_clues.len(), best_used.len())
+                }
+                None => true,
+            };
+            if simpler {
+                best = Some((i, used, clues));
+            }
+        }
+
+        let (i, _, clues) = best.unwrap();
+        let value = unknown.remove(i);
+        let house = solution.position(value.category, value.value);
+        premises.push(checker.add(&Clue::At(value, house)));
+        behind.push(clues.clone());
+        steps.push(Step {
+            value,
+            house,
+            clues,
+        });
+    }
+
+    Ok(steps)
+}
+
+#[cfg(test)]
+#[test]
+fn test_explain() {
+    use super::definition::EINSTEIN;
+
+    let puzzle: Puzzle = EINSTEIN.parse().unwrap();
+    let definition = &puzzle.definition;
+    let steps = explain(&puzzle).unwrap();
+    assert_eq!(steps.len(), 25);
+    assert_eq!(steps[0].text(definition), "House 1 is Norwegian (clue 9)");
+    assert!(steps
+        .iter()
+        .any(|step| step.text(definition) == "House 2 is Blue (clues 9, 14)"));
+    let fish = steps
+        .iter()
+        .find(|step| definition.value(step.value.category, step.value.value) == "Fish")
+        .unwrap();
+    assert_eq!(fish.house, 3);
+
+    // Each step follows from the clues it cites alone
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let mut checker = Checker::new(&ctx, &puzzle, Encoding::OneHot);
+    for step in &steps {
+        let fact = Clue::At(step.value, step.house);
+        assert!(
+            checker.implies(&step.clues, &fact).is_some(),
+            "{}",
+            step.text(definition)
+        );
+    }
+
+    let mut open = puzzle.clone();
+    open.clues.truncate(8);
+    assert!(explain(&open).is_err());
+}
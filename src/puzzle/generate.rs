@@ -0,0 +1,269 @@
+use anyhow::bail;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use z3::{Config, Context};
+
+use super::{
+    clues::{Clue, Puzzle, Value},
+    definition::{Category, Definition},
+    street::{Encoding, Solution},
+    unique::Checker,
+};
+
+/// Names of the first categories of generated puzzles, then `category6`, `category7`...
+const CATEGORIES: [&str; 5] = ["color", "nationality", "beverage", "cigar", "pet"];
+
+/// `houses` houses and `categories` categories, values named after their category
+/// (`color1`, `color2`...)
+pub fn definition(houses: usize, categories: usize) -> Definition {
+    Definition {
+        houses,
+        categories: (0..categories)
+            .map(|c| {
+                let name = CATEGORIES
+                    .get(c)
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| format!("category{}", c + 1));
+                Category {
+                    values: (1..=houses).map(|v| format!("{}{}", name, v)).collect(),
+                    name,
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Each category shuffled along the street
+pub fn grid(rng: &mut StdRng, definition: &Definition) -> Solution {
+    let columns: Vec<Vec<usize>> = definition
+        .categories
+        .iter()
+        .map(|_| {
+            let mut column: Vec<usize> = (0..definition.houses).collect();
+            column.shuffle(rng);
+            column
+        })
+        .collect();
+
+    Solution {
+        houses: (0..definition.houses)
+            .map(|h| columns.iter().map(|column| column[h]).collect())
+            .collect(),
+    }
+}
+
+/// Any clue on three different values of `definition` at most, true or not
+pub fn clue(rng: &mut StdRng, definition: &Definition) -> Clue {
+    let mut values: Vec<Value> = Vec::new();
+    while values.len() < 3 {
+        let value = Value {
+            category: rng.gen_range(0..definition.categories.len()),
+            value: rng.gen_range(0..definition.houses),
+        };
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    let (a, b, c) = (values[0], values[1], values[2]);
+
+    match rng.gen_range(0..11) {
+        0 => Clue::Same(a, b),
+        1 => Clue::LeftOf(a, b),
+        2 => Clue::RightOf(a, b),
+        3 => Clue::SomewhereLeftOf(a, b),
+        4 => Clue::NextTo(a, b),
+        5 if definition.houses > 2 => Clue::Apart(a, b, rng.gen_range(2..definition.houses)),
+        6 => Clue::At(a, rng.gen_range(0..definition.houses)),
+        7 => Clue::AtEnd(a),
+        8 => Clue::Between(a, b, c),
+        9 => Clue::Not(Box::new(Clue::Same(a, b))),
+        _ => Clue::Not(Box::new(Clue::NextTo(a, b))),
+    }
+}
+
+/// A generated puzzle, built around a hidden grid every clue holds on, so it has at least
+/// that solution
+#[derive(Debug, Clone)]
+pub struct Planted {
+    pub seed: u64,
+    pub puzzle: Puzzle,
+    pub hidden: Solution,
+}
+
+impl Planted {
+    /// Same seed and sizes, same puzzle
+    pub fn generate(
+        seed: u64,
+        houses: usize,
+        categories: usize,
+        clues: usize,
+    ) -> anyhow::Result<Self> {
+        if houses < 2 || categories == 0 || houses * categories < 3 {
+            bail!("a puzzle needs at least two houses, a category and three values");
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let definition = definition(houses, categories);
+        let hidden = grid(&mut rng, &definition);
+
+        // Draw clues until enough of them hold on the hidden grid
+        let mut picked: Vec<Clue> = Vec::new();
+        let mut attempts = 0;
+        while picked.len() < clues {
+            attempts += 1;
+            if attempts > clues * 100 {
+                bail!("could not draw {} distinct clues", clues);
+            }
+
+            let clue = clue(&mut rng, &definition);
+            if clue.holds(&hidden) && !picked.contains(&clue) {
+                picked.push(clue);
+            }
+        }
+
+        Ok(Planted {
+            seed,
+            puzzle: Puzzle {
+                definition,
+                clues: picked,
+            },
+            hidden,
+        })
+    }
+
+    /// Clues drawn until the hidden grid is the only solution, then pruned so that none can be
+    /// left out. Same seed and sizes, same puzzle.
+    pub fn unique(seed: u64, houses: usize, categories: usize) -> anyhow::Result<Self> {
+        if houses < 2 || categories == 0 || houses * categories < 3 {
+            bail!("a puzzle needs at least two houses, a category and three values");
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let definition = definition(houses, categories);
+        let hidden = grid(&mut rng, &definition);
+
+        let mut puzzle = Puzzle {
+            definition,
+            clues: Vec::new(),
+        };
+        // One-hot is the fastest encoding in `bench --zebra`
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let mut checker = Checker::new(&ctx, &puzzle, Encoding::OneHot);
+
+        // Any other grid the clues so far allow, the next clue rules it out
+        let mut added: Vec<usize> = Vec::new();
+        while let Some(other) = checker.solve(&added, Some(&hidden)) {
+            let mut attempts = 0;
+            let clue = loop {
+                attempts += 1;
+                if attempts > 10_000 {
+                    bail!("no clue tells the hidden grid from another");
+                }
+                let clue = clue(&mut rng, &puzzle.definition);
+                if clue.holds(&hidden) && !clue.holds(&other) {
+                    break clue;
+                }
+            };
+            added.push(checker.add(&clue));
+            puzzle.clues.push(clue);
+        }
+
+        let kept = checker.prune(&added, &hidden);
+        puzzle.clues = kept.iter().map(|&i| puzzle.clues[i].clone()).collect();
+
+        Ok(Planted {
+            seed,
+            puzzle,
+            hidden,
+        })
+    }
+
+    /// The puzzle in the clue format, the seed in a comment
+    pub fn text(&self) -> String {
+        format!(
+            "# Generated with seed {}, {} houses, {} categories\n{}",
+            self.seed,
+            self.puzzle.definition.houses,
+            self.puzzle.definition.categories.len(),
+            self.puzzle.text()
+        )
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_generate() {
+    let definition = definition(7, 6);
+    assert_eq!(definition.categories[0].values[6], "color7");
+    assert_eq!(definition.categories[5].name, "category6");
+    assert_eq!(
+        definition.to_string().parse::<Definition>().unwrap(),
+        definition
+    );
+
+    let planted = Planted::generate(4, 6, 5, 20).unwrap();
+    assert_eq!(planted.puzzle.clues.len(), 20);
+    assert!(planted
+        .puzzle
+        .clues
+        .iter()
+        .all(|clue| clue.holds(&planted.hidden)));
+    assert_eq!(
+        planted.puzzle.text(),
+        Planted::generate(4, 6, 5, 20).unwrap().puzzle.text()
+    );
+    assert_ne!(
+        planted.puzzle.text(),
+        Planted::generate(5, 6, 5, 20).unwrap().puzzle.text()
+    );
+
+    // Generated clues read back from their text
+    let text = planted.puzzle.text();
+    assert_eq!(text.parse::<Puzzle>().unwrap().clues, planted.puzzle.clues);
+
+    assert!(Planted::generate(1, 1, 5, 3).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_generate_unique() {
+    use super::street::Street;
+    use z3::{SatResult, Solver};
+
+    for (seed, houses, categories) in [(1, 3, 2), (2, 4, 3), (3, 5, 3)] {
+        let planted = Planted::unique(seed, houses, categories).unwrap();
+        let text = planted.text();
+        assert!(text.starts_with(&format!("# Generated with seed {}", seed)));
+        assert_eq!(
+            text,
+            Planted::unique(seed, houses, categories).unwrap().text()
+        );
+
+        // Read back, solved from scratch: the hidden grid and nothing else
+        let puzzle: Puzzle = text.parse().unwrap();
+        assert_eq!(puzzle.clues, planted.puzzle.clues);
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let solver = Solver::new(&ctx);
+        let street = Street::new(&ctx, &puzzle.definition, Encoding::INT);
+        for constraint in street.constraints() {
+            solver.assert(&constraint);
+        }
+        for clue in &puzzle.clues {
+            for constraint in clue.constraints(&street) {
+                solver.assert(&constraint);
+            }
+        }
+        assert_eq!(solver.check(), SatResult::Sat);
+        assert_eq!(street.decode(&solver.get_model().unwrap()), planted.hidden);
+        solver.assert(&street.matches(&planted.hidden).not());
+        assert_eq!(solver.check(), SatResult::Unsat);
+
+        // Pruned: every clue is needed
+        let checker = Checker::new(&ctx, &puzzle, Encoding::OneHot);
+        for clue in 0..puzzle.clues.len() {
+            let others: Vec<usize> = (0..puzzle.clues.len()).filter(|&c| c != clue).collect();
+            assert!(!checker.unique(&others, &planted.hidden));
+        }
+    }
+}
@@ -0,0 +1,10 @@
+// Shared between the logic grid puzzle binaries, not every bin uses every helper
+#![allow(dead_code)]
+
+pub mod clues;
+pub mod definition;
+pub mod english;
+pub mod explain;
+pub mod generate;
+pub mod street;
+pub mod unique;
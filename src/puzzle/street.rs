@@ -0,0 +1,564 @@
+use std::{fmt, rc::Rc, str::FromStr};
+
+use anyhow::anyhow;
+use z3::{
+    ast::{self, Ast, Bool, Dynamic, Int},
+    Context, DatatypeBuilder, DatatypeSort, FuncDecl, Model, Sort,
+};
+
+use super::definition::{Category, Definition};
+use crate::domain::{self as values, Domain as Values};
+
+/// How the attributes of the houses are handed to z3. Positions are `Int`s whatever the encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// A variable per house and category: `Int`s (bounded, with `!=` between every two houses as
+    /// `einstein` does) or bit-vectors in the shared domains, or one enumeration datatype per
+    /// category, a constructor per value (no bounds, and models print the value names)
+    Values(values::Encoding),
+    /// The same `Int`s, a single `distinct` per category instead of the pairs
+    Distinct,
+    /// A Boolean per house and value: exactly one per house and one per value is true
+    OneHot,
+    /// Houses in street order, an uninterpreted function per category from position to value
+    Function,
+}
+
+impl Encoding {
+    pub const INT: Encoding = Encoding::Values(values::Encoding::Int);
+    pub const BIT_VEC: Encoding = Encoding::Values(values::Encoding::BitVec);
+    pub const ENUM: Encoding = Encoding::Values(values::Encoding::Enum);
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Encoding::INT
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s.trim() {
+            "distinct" => Encoding::Distinct,
+            "onehot" => Encoding::OneHot,
+            "uf" | "function" => Encoding::Function,
+            other => Encoding::Values(other.parse().map_err(|_| {
+                anyhow!(
+                    "unknown encoding `{}` (int, distinct, bv, onehot, uf, enum)",
+                    other
+                )
+            })?),
+        })
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Encoding::Values(encoding) => write!(f, "{}", encoding),
+            Encoding::Distinct => write!(f, "distinct"),
+            Encoding::OneHot => write!(f, "onehot"),
+            Encoding::Function => write!(f, "uf"),
+        }
+    }
+}
+
+pub const ENCODINGS: [Encoding; 6] = [
+    Encoding::INT,
+    Encoding::Distinct,
+    Encoding::BIT_VEC,
+    Encoding::OneHot,
+    Encoding::Function,
+    Encoding::ENUM,
+];
+
+/// The value of one category in a house
+enum Field<'ctx> {
+    Var(Dynamic<'ctx>),
+    /// One Boolean per value
+    OneHot(Vec<Bool<'ctx>>),
+}
+
+/// A category in some encoding: how its fields are made, constrained and decoded
+struct Domain<'ctx> {
+    ctx: &'ctx Context,
+    encoding: Encoding,
+    size: usize,
+    /// `Int`s (for `Distinct` and `Function` too) or bit-vectors
+    values: Option<Values<'ctx>>,
+    /// The category's enumeration, with `Encoding::ENUM`
+    datatype: Option<DatatypeSort<'ctx>>,
+    /// Position to value, with `Encoding::Function`
+    function: Option<FuncDecl<'ctx>>,
+}
+
+impl<'ctx> Domain<'ctx> {
+    /// Enumeration sorts and functions are named after their category, unique in a definition
+    fn new(ctx: &'ctx Context, encoding: Encoding, category: &Category) -> Self {
+        let size = category.values.len();
+        let values = match encoding {
+            Encoding::Values(values::Encoding::Enum) | Encoding::OneHot => None,
+            Encoding::Values(encoding) => Some(Values::new(ctx, encoding, &category.name, size)),
+            Encoding::Distinct | Encoding::Function => Some(Values::new(
+                ctx,
+                values::Encoding::Int,
+                &category.name,
+                size,
+            )),
+        };
+        let datatype = (encoding == Encoding::ENUM).then(|| {
+            category
+                .values
+                .iter()
+                .fold(
+                    DatatypeBuilder::new(ctx, category.name.as_str()),
+                    |builder, value| builder.variant(value, Vec::new()),
+                )
+                .finish()
+        });
+        let function = (encoding == Encoding::Function).then(|| {
+            FuncDecl::new(
+                ctx,
+                category.name.as_str(),
+                &[&Sort::int(ctx)],
+                &Sort::int(ctx),
+            )
+        });
+
+        Domain {
+            ctx,
+            encoding,
+            size,
+            values,
+            datatype,
+            function,
+        }
+    }
+
+    /// The field of house `name` at position `index`
+    fn field(&self, name: &str, index: &Int<'ctx>) -> Field<'ctx> {
+        match (&self.function, &self.values, &self.datatype) {
+            (Some(function), _, _) => Field::Var(function.apply(&[index])),
+            (None, Some(values), _) => Field::Var(values.var(name)),
+            (None, None, Some(datatype)) => {
+                Field::Var(FuncDecl::new(self.ctx, name, &[], &datatype.sort).apply(&[]))
+            }
+            (None, None, None) => Field::OneHot(
+                (0..self.size)
+                    .map(|v| ast::Bool::new_const(self.ctx, format!("{}_{}", name, v)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn is(&self, field: &Field<'ctx>, value: usize) -> Bool<'ctx> {
+        match (field, &self.datatype) {
+            (Field::Var(var), Some(datatype)) => {
+                var._eq(&datatype.variants[value].constructor.apply(&[]))
+            }
+            (Field::Var(var), None) => self.values().is(var, value),
+            (Field::OneHot(values), _) => values[value].clone(),
+        }
+    }
+
+    fn values(&self) -> &Values<'ctx> {
+        self.values.as_ref().expect("variables have a value domain")
+    }
+
+    /// Every house takes a different value of the domain
+    fn constraints(&self, fields: &[&Field<'ctx>]) -> Vec<Bool<'ctx>> {
+        if self.encoding == Encoding::OneHot {
+            let exactly_one = |bools: Vec<&Bool<'ctx>>| {
+                let weighted: Vec<(&Bool<'ctx>, i32)> = bools.into_iter().map(|b| (b, 1)).collect();
+                ast::Bool::pb_eq(self.ctx, &weighted, 1)
+            };
+            let bits: Vec<&Vec<Bool<'ctx>>> = fields
+                .iter()
+                .filter_map(|field| match field {
+                    Field::OneHot(values) => Some(values),
+                    Field::Var(_) => None,
+                })
+                .collect();
+
+            let mut constraints: Vec<Bool<'ctx>> = bits
+                .iter()
+                .map(|values| exactly_one(values.iter().collect()))
+                .collect();
+            for value in 0..self.size {
+                constraints.push(exactly_one(
+                    bits.iter().map(|values| &values[value]).collect(),
+                ));
+            }
+            return constraints;
+        }
+
+        let vars: Vec<Dynamic<'ctx>> = fields
+            .iter()
+            .filter_map(|field| match field {
+                Field::Var(var) => Some(var.clone()),
+                Field::OneHot(_) => None,
+            })
+            .collect();
+        let mut constraints = different(self.ctx, &vars, self.encoding == Encoding::INT);
+        if let Some(values) = &self.values {
+            constraints.extend(vars.iter().filter_map(|var| values.bounds(var)));
+        }
+        constraints
+    }
+
+    /// Enumeration values are found by constructor name
+    fn decode(&self, model: &Model<'ctx>, field: &Field<'ctx>) -> usize {
+        match (field, &self.datatype) {
+            (Field::Var(var), Some(datatype)) => {
+                let name = model.eval(var, true).unwrap().decl().name();
+                datatype
+                    .variants
+                    .iter()
+                    .position(|variant| variant.constructor.name() == name)
+                    .expect("a constructor of the category")
+            }
+            (Field::Var(var), None) => self.values().decode(model, var),
+            (Field::OneHot(values), _) => values
+                .iter()
+                .position(|value| model.eval(value, true).unwrap().as_bool() == Some(true))
+                .expect("one value per house"),
+        }
+    }
+}
+
+/// No two of `vars` are equal: `!=` pair by pair, or a single `distinct`
+fn different<'ctx>(ctx: &'ctx Context, vars: &[Dynamic<'ctx>], pairwise: bool) -> Vec<Bool<'ctx>> {
+    if !pairwise {
+        let refs: Vec<&Dynamic> = vars.iter().collect();
+        return vec![Dynamic::distinct(ctx, &refs)];
+    }
+
+    let mut constraints = Vec::new();
+    for (i, var) in vars.iter().enumerate() {
+        for other in vars.iter().skip(i + 1) {
+            constraints.push(var._eq(other).not());
+        }
+    }
+    constraints
+}
+
+/// Every position within [0, houses)
+fn bounds<'ctx>(ctx: &'ctx Context, positions: &[Int<'ctx>], houses: usize) -> Vec<Bool<'ctx>> {
+    let houses = ast::Int::from_u64(ctx, houses as u64);
+    positions
+        .iter()
+        .flat_map(|position| {
+            [
+                position.ge(&ast::Int::from_u64(ctx, 0)),
+                position.lt(&houses),
+            ]
+        })
+        .collect()
+}
+
+/// z3 variables of one house: its position in the street and the value of each
+/// category (value v of a category is its v-th value in the definition)
+pub struct House<'ctx> {
+    ctx: &'ctx Context,
+    pub index: Int<'ctx>,
+    /// In the order of the definition's categories
+    attributes: Vec<Field<'ctx>>,
+    domains: Rc<Vec<Domain<'ctx>>>,
+}
+
+impl<'ctx> House<'ctx> {
+    /// With `Encoding::Function` house `id` is the one at position `id`
+    fn new(
+        ctx: &'ctx Context,
+        definition: &Definition,
+        id: usize,
+        encoding: Encoding,
+        domains: Rc<Vec<Domain<'ctx>>>,
+    ) -> Self {
+        let index = match encoding {
+            Encoding::Function => ast::Int::from_u64(ctx, id as u64),
+            _ => ast::Int::new_const(ctx, format!("house{}_index", id)),
+        };
+
+        House {
+            ctx,
+            attributes: definition
+                .categories
+                .iter()
+                .zip(domains.iter())
+                .map(|(category, domain)| {
+                    domain.field(&format!("house{}_{}", id, category.name), &index)
+                })
+                .collect(),
+            index,
+            domains,
+        }
+    }
+
+    fn int(&self, value: usize) -> Int<'ctx> {
+        ast::Int::from_u64(self.ctx, value as u64)
+    }
+
+    /// The house has value `value` of `category`
+    pub fn is(&self, category: usize, value: usize) -> Bool<'ctx> {
+        self.domains[category].is(&self.attributes[category], value)
+    }
+
+    /// The house is at `position`, 0 being the first one
+    pub fn at(&self, position: usize) -> Bool<'ctx> {
+        self.index._eq(&self.int(position))
+    }
+
+    /// First or last of a street of `houses`
+    pub fn at_end(&self, houses: usize) -> Bool<'ctx> {
+        self.at(0) | self.at(houses - 1)
+    }
+
+    /// Immediately left of `other`
+    pub fn left_of(&self, other: &House<'ctx>) -> Bool<'ctx> {
+        self.index._eq(&(&other.index - &self.int(1)))
+    }
+
+    /// Immediately right of `other`
+    pub fn right_of(&self, other: &House<'ctx>) -> Bool<'ctx> {
+        other.left_of(self)
+    }
+
+    /// Anywhere left of `other`
+    pub fn somewhere_left_of(&self, other: &House<'ctx>) -> Bool<'ctx> {
+        self.index.lt(&other.index)
+    }
+
+    /// Anywhere right of `other`
+    pub fn somewhere_right_of(&self, other: &House<'ctx>) -> Bool<'ctx> {
+        other.somewhere_left_of(self)
+    }
+
+    /// Exactly `distance` houses from `other`, on either side
+    pub fn apart(&self, other: &House<'ctx>, distance: usize) -> Bool<'ctx> {
+        let distance = self.int(distance);
+        self.index._eq(&(&other.index + &distance)) | self.index._eq(&(&other.index - &distance))
+    }
+
+    /// One side or the other of `other`
+    pub fn next_to(&self, other: &House<'ctx>) -> Bool<'ctx> {
+        self.apart(other, 1)
+    }
+
+    /// Somewhere between `one` and `other`, whichever is left
+    pub fn between(&self, one: &House<'ctx>, other: &House<'ctx>) -> Bool<'ctx> {
+        (one.somewhere_left_of(self) & self.somewhere_left_of(other))
+            | (other.somewhere_left_of(self) & self.somewhere_left_of(one))
+    }
+}
+
+/// The houses of a puzzle. Houses are not sorted: house i is wherever its index says.
+pub struct Street<'ctx> {
+    ctx: &'ctx Context,
+    pub encoding: Encoding,
+    pub houses: Vec<House<'ctx>>,
+}
+
+impl<'ctx> Street<'ctx> {
+    pub fn new(ctx: &'ctx Context, definition: &Definition, encoding: Encoding) -> Self {
+        let domains = Rc::new(
+            definition
+                .categories
+                .iter()
+                .map(|category| Domain::new(ctx, encoding, category))
+                .collect::<Vec<_>>(),
+        );
+
+        Street {
+            ctx,
+            encoding,
+            houses: (0..definition.houses)
+                .map(|id| House::new(ctx, definition, id, encoding, domains.clone()))
+                .collect(),
+        }
+    }
+
+    /// Every house has a different position within [0, houses) (given with
+    /// `Encoding::Function`), and a different value of each category
+    pub fn constraints(&self) -> Vec<Bool<'ctx>> {
+        let mut constraints = Vec::new();
+
+        if self.encoding != Encoding::Function {
+            let indices: Vec<Int<'ctx>> = self.houses.iter().map(|h| h.index.clone()).collect();
+            let vars: Vec<Dynamic<'ctx>> = indices.iter().map(|i| Dynamic::from_ast(i)).collect();
+            constraints.extend(different(self.ctx, &vars, self.encoding == Encoding::INT));
+            constraints.extend(bounds(self.ctx, &indices, self.houses.len()));
+        }
+
+        if let Some(first) = self.houses.first() {
+            for (c, domain) in first.domains.iter().enumerate() {
+                let fields: Vec<&Field<'ctx>> =
+                    self.houses.iter().map(|h| &h.attributes[c]).collect();
+                constraints.extend(domain.constraints(&fields));
+            }
+        }
+
+        constraints
+    }
+
+    /// The street looks like `solution`, whichever house variables end up where
+    pub fn matches(&self, solution: &Solution) -> Bool<'ctx> {
+        let positions: Vec<Bool<'ctx>> = solution
+            .houses
+            .iter()
+            .enumerate()
+            .map(|(position, values)| {
+                let candidates: Vec<Bool<'ctx>> = self
+                    .houses
+                    .iter()
+                    .map(|house| {
+                        values
+                            .iter()
+                            .enumerate()
+                            .fold(house.at(position), |all, (c, &v)| all & house.is(c, v))
+                    })
+                    .collect();
+                let refs: Vec<&Bool> = candidates.iter().collect();
+                ast::Bool::or(self.ctx, &refs)
+            })
+            .collect();
+        let refs: Vec<&Bool> = positions.iter().collect();
+        ast::Bool::and(self.ctx, &refs)
+    }
+
+    pub fn decode(&self, model: &Model<'ctx>) -> Solution {
+        let mut houses: Vec<(u64, Vec<usize>)> = self
+            .houses
+            .iter()
+            .map(|house| {
+                (
+                    model.eval(&house.index, true).unwrap().as_u64().unwrap(),
+                    house
+                        .attributes
+                        .iter()
+                        .zip(house.domains.iter())
+                        .map(|(field, domain)| domain.decode(model, field))
+                        .collect(),
+                )
+            })
+            .collect();
+        houses.sort_by_key(|(index, _)| *index);
+
+        Solution {
+            houses: houses.into_iter().map(|(_, values)| values).collect(),
+        }
+    }
+}
+
+/// Value index of every category, house by house from the first one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub houses: Vec<Vec<usize>>,
+}
+
+impl Solution {
+    /// Position of the house with value `value` of `category`
+    pub fn position(&self, category: usize, value: usize) -> usize {
+        self.houses
+            .iter()
+            .position(|house| house[category] == value)
+            .expect("every value is in some house")
+    }
+
+    /// One row per category, one column per house
+    pub fn render(&self, definition: &Definition) -> String {
+        let mut rows: Vec<Vec<String>> = vec![std::iter::once("house".to_string())
+            .chain((1..=self.houses.len()).map(|n| n.to_string()))
+            .collect()];
+        for (c, category) in definition.categories.iter().enumerate() {
+            rows.push(
+                std::iter::once(category.name.clone())
+                    .chain(
+                        self.houses
+                            .iter()
+                            .map(|house| definition.value(c, house[c]).to_string()),
+                    )
+                    .collect(),
+            );
+        }
+
+        let widths: Vec<usize> = (0..rows[0].len())
+            .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap())
+            .collect();
+
+        let mut out = String::new();
+        for row in &rows {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            out.push_str(cells.join(" | ").trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_street() {
+    use z3::{Config, SatResult, Solver};
+
+    let definition: Definition = "color: red, blue, green\npet: cat, dog, fish"
+        .parse()
+        .unwrap();
+    let cfg = Config::new();
+
+    for encoding in ENCODINGS {
+        let ctx = Context::new(&cfg);
+        let solver = Solver::new(&ctx);
+        let street = Street::new(&ctx, &definition, encoding);
+        for constraint in street.constraints() {
+            solver.assert(&constraint);
+        }
+
+        // Every arrangement of both categories, 3! x 3!
+        let mut solutions = Vec::new();
+        while solver.check() == SatResult::Sat {
+            let model = solver.get_model().unwrap();
+            let solution = street.decode(&model);
+
+            for c in 0..2 {
+                let mut column: Vec<usize> = solution.houses.iter().map(|house| house[c]).collect();
+                column.sort();
+                assert_eq!(column, [0, 1, 2], "{}", encoding);
+            }
+            assert!(!solutions.contains(&solution));
+
+            // Block this grid whatever the order of the house variables
+            solver.assert(&street.matches(&solution).not());
+
+            solutions.push(solution);
+        }
+        assert_eq!(solutions.len(), 36, "{}", encoding);
+
+        let render = solutions[0].render(&definition);
+        assert_eq!(render.lines().count(), 3);
+        assert!(render.starts_with("house | 1"));
+    }
+
+    // Enumeration values print as their name
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
+    let street = Street::new(&ctx, &definition, Encoding::ENUM);
+    for constraint in street.constraints() {
+        solver.assert(&constraint);
+    }
+    solver.assert(&street.houses[0].is(1, 2));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    assert!(
+        model.to_string().contains("house0_pet -> fish"),
+        "{}",
+        model
+    );
+    assert!("bits".parse::<Encoding>().is_err());
+}
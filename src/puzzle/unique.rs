@@ -0,0 +1,247 @@
+use anyhow::bail;
+use z3::{
+    ast::{self, Bool},
+    Config, Context, SatResult, Solver,
+};
+
+use super::{
+    clues::{Clue, Puzzle},
+    street::{Encoding, Solution, Street},
+};
+
+/// One solver for a street and its clues, each clue asserted behind a guard literal so that
+/// any subset of them can be checked without starting over
+pub struct Checker<'ctx> {
+    ctx: &'ctx Context,
+    solver: Solver<'ctx>,
+    street: Street<'ctx>,
+    guards: Vec<Bool<'ctx>>,
+    /// Claims checked by `implies`, each guarding its negation
+    claims: Vec<(Clue, Bool<'ctx>)>,
+}
+
+impl<'ctx> Checker<'ctx> {
+    pub fn new(ctx: &'ctx Context, puzzle: &Puzzle, encoding: Encoding) -> Self {
+        let solver = Solver::new(ctx);
+        let street = Street::new(ctx, &puzzle.definition, encoding);
+        for constraint in street.constraints() {
+            solver.assert(&constraint);
+        }
+
+        let mut checker = Checker {
+            ctx,
+            solver,
+            street,
+            guards: Vec::new(),
+            claims: Vec::new(),
+        };
+        for clue in &puzzle.clues {
+            checker.add(clue);
+        }
+        checker
+    }
+
+    /// Guard a new clue, its number for `solve`
+    pub fn add(&mut self, clue: &Clue) -> usize {
+        let guard = ast::Bool::new_const(self.ctx, format!("clue{}", self.guards.len()));
+        for constraint in clue.constraints(&self.street) {
+            self.solver.assert(&guard.implies(&constraint));
+        }
+        self.guards.push(guard);
+        self.guards.len() - 1
+    }
+
+    /// A grid allowed by the clues numbered in `clues`, other than `besides` if given
+    pub fn solve(&self, clues: &[usize], besides: Option<&Solution>) -> Option<Solution> {
+        let assumptions: Vec<Bool<'ctx>> = clues.iter().map(|&i| self.guards[i].clone()).collect();
+
+        self.solver.push();
+        if let Some(solution) = besides {
+            self.solver.assert(&self.street.matches(solution).not());
+        }
+        let found = match self.solver.check_assumptions(&assumptions) {
+            SatResult::Sat => Some(self.street.decode(&self.solver.get_model().unwrap())),
+            _ => None,
+        };
+        self.solver.pop(1);
+
+        found
+    }
+
+    /// `solution` is the only grid the clues numbered in `clues` allow (it is assumed to be one)
+    pub fn unique(&self, clues: &[usize], solution: &Solution) -> bool {
+        self.solve(clues, Some(solution)).is_none()
+    }
+
+    /// Whether the clues numbered in `clues` leave no grid where `claim` is false, and if so a
+    /// subset of them that is enough, each of its clues needed
+    pub fn implies(&mut self, clues: &[usize], claim: &Clue) -> Option<Vec<usize>> {
+        // Asserted once behind a guard rather than in a push: z3 keeps what it learnt
+        let negated = match self.claims.iter().find(|(known, _)| known == claim) {
+            Some((_, guard)) => guard.clone(),
+            None => {
+                let guard = ast::Bool::new_const(self.ctx, format!("claim{}", self.claims.len()));
+                for constraint in Clue::Not(Box::new(claim.clone())).constraints(&self.street) {
+                    self.solver.assert(&guard.implies(&constraint));
+                }
+                self.claims.push((claim.clone(), guard.clone()));
+                guard
+            }
+        };
+        let check = |clues: &[usize]| {
+            let mut assumptions: Vec<Bool<'ctx>> =
+                clues.iter().map(|&i| self.guards[i].clone()).collect();
+            assumptions.push(negated.clone());
+            self.solver.check_assumptions(&assumptions)
+        };
+        // The clues of the last unsat core, which is not always minimal
+        let core = |clues: &[usize]| -> Vec<usize> {
+            let core = self.solver.get_unsat_core();
+            clues
+                .iter()
+                .copied()
+                .filter(|&i| core.contains(&self.guards[i]))
+                .collect()
+        };
+
+        if check(clues) != SatResult::Unsat {
+            return None;
+        }
+        let mut needed = core(clues);
+        let mut i = 0;
+        while i < needed.len() {
+            let without: Vec<usize> = [&needed[..i], &needed[i + 1..]].concat();
+            if check(&without) == SatResult::Unsat {
+                needed = core(&without);
+            } else {
+                i += 1;
+            }
+        }
+        Some(needed)
+    }
+
+    /// Drop clues one by one, in order, as long as `solution` stays the only grid:
+    /// no clue of what is left can go without letting another grid in
+    pub fn prune(&self, clues: &[usize], solution: &Solution) -> Vec<usize> {
+        let mut kept = clues.to_vec();
+        for clue in clues {
+            let without: Vec<usize> = kept.iter().copied().filter(|c| c != clue).collect();
+            if self.unique(&without, solution) {
+                kept = without;
+            }
+        }
+        kept
+    }
+}
+
+/// Which clues of a puzzle with a single solution it could do without, clue numbers from 0
+#[derive(Debug)]
+pub struct Analysis {
+    pub solution: Solution,
+    /// Each of them alone can go, the others still force the solution (not all of them at once)
+    pub redundant: Vec<usize>,
+    /// Enough clues for the solution, none of which can go: minimal, not always the smallest
+    pub minimal: Vec<usize>,
+}
+
+/// Fails unless the puzzle has exactly one solution
+pub fn analyze(puzzle: &Puzzle, encoding: Encoding) -> anyhow::Result<Analysis> {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let checker = Checker::new(&ctx, puzzle, encoding);
+
+    let all: Vec<usize> = (0..puzzle.clues.len()).collect();
+    let Some(solution) = checker.solve(&all, None) else {
+        bail!("the clues have no solution");
+    };
+    if !checker.unique(&all, &solution) {
+        bail!("the clues have more than one solution");
+    }
+
+    let redundant = all
+        .iter()
+        .copied()
+        .filter(|&clue| {
+            let others: Vec<usize> = all.iter().copied().filter(|&c| c != clue).collect();
+            checker.unique(&others, &solution)
+        })
+        .collect();
+    let minimal = checker.prune(&all, &solution);
+
+    Ok(Analysis {
+        solution,
+        redundant,
+        minimal,
+    })
+}
+
+#[cfg(test)]
+#[test]
+fn test_unique() {
+    use super::definition::EINSTEIN;
+
+    let puzzle: Puzzle = EINSTEIN.parse().unwrap();
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let mut checker = Checker::new(&ctx, &puzzle, Encoding::OneHot);
+
+    let all: Vec<usize> = (0..puzzle.clues.len()).collect();
+    let solution = checker.solve(&all, None).unwrap();
+    assert!(checker.unique(&all, &solution));
+
+    // Without "Norwegian at 1" (clue 9) other streets fit
+    let without: Vec<usize> = all.iter().copied().filter(|&c| c != 8).collect();
+    let other = checker.solve(&without, Some(&solution)).unwrap();
+    assert_ne!(other, solution);
+    assert!(!checker.unique(&without, &solution));
+
+    // A clue the solution breaks leaves nothing
+    let wrong = checker.add(&Clue::parse("Norwegian at 2", &puzzle.definition).unwrap());
+    assert!(checker.solve(&[wrong], None).is_some());
+    assert!(checker
+        .solve(&[all.clone(), vec![wrong]].concat(), None)
+        .is_none());
+
+    let kept = checker.prune(&all, &solution);
+    assert!(kept.len() < all.len());
+    assert!(checker.unique(&kept, &solution));
+    for clue in &kept {
+        let fewer: Vec<usize> = kept.iter().copied().filter(|c| c != clue).collect();
+        assert!(!checker.unique(&fewer, &solution));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_analyze() {
+    use super::definition::EINSTEIN;
+
+    // The last of the classic clues follows from the others
+    let mut puzzle: Puzzle = EINSTEIN.parse().unwrap();
+    let analysis = analyze(&puzzle, Encoding::OneHot).unwrap();
+    assert_eq!(analysis.redundant, [14]);
+    assert_eq!(analysis.minimal, (0..14).collect::<Vec<_>>());
+
+    // With the answer itself as a clue, the ones only there to reach it can go, one at a time
+    let fish = Clue::parse("German = Fish", &puzzle.definition).unwrap();
+    puzzle.clues.push(fish);
+    let analysis = analyze(&puzzle, Encoding::INT).unwrap();
+    assert_eq!(analysis.redundant, [1, 2, 9, 10, 14, 15]);
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let checker = Checker::new(&ctx, &puzzle, Encoding::OneHot);
+    assert!(checker.unique(&analysis.minimal, &analysis.solution));
+    assert_eq!(
+        checker.prune(&analysis.minimal, &analysis.solution),
+        analysis.minimal
+    );
+    assert!(analysis.minimal.len() < 15);
+
+    puzzle.clues.truncate(8);
+    let err = analyze(&puzzle, Encoding::OneHot).unwrap_err();
+    assert_eq!(err.to_string(), "the clues have more than one solution");
+    puzzle
+        .clues
+        .push(Clue::parse("Norwegian at 2", &puzzle.definition).unwrap());
+    assert!(analyze(&puzzle, Encoding::OneHot).is_err());
+}
@@ -0,0 +1,17 @@
+/// Every value following `flag` on the command line
+pub fn flag_values(args: &[String], flag: &str) -> Vec<String> {
+    args.windows(2)
+        .filter(|pair| pair[0] == flag)
+        .map(|pair| pair[1].clone())
+        .collect()
+}
+
+/// The last value following `flag`
+pub fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    flag_values(args, flag).pop()
+}
+
+/// A flag without a value, like `--names`
+pub fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| arg == flag)
+}
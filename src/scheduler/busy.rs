@@ -0,0 +1,259 @@
+use std::fs;
+
+use anyhow::{anyhow, Context};
+use chrono::NaiveDateTime;
+use chrono_tz::Tz;
+
+use super::{
+    ics::{self, Event, IcsConfig},
+    schedule::Meeting,
+};
+
+/// Whose calendar an .ics file describes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Owner {
+    Participant(String),
+    Room(i64),
+}
+
+impl Owner {
+    /// `R2` is a room, anything else is a participant name
+    pub fn parse(name: &str) -> Self {
+        match name.strip_prefix('R').map(str::parse) {
+            Some(Ok(room)) => Owner::Room(room),
+            _ => Owner::Participant(name.to_string()),
+        }
+    }
+}
+
+/// A single busy interval [start, end), recurrences already expanded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Busy {
+    pub owner: Owner,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+/// A timeslot a meeting, or a room, is not available in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blocked {
+    /// Meeting index (M1 is 0) cannot be held in that slot
+    Meeting { meeting: usize, am: bool },
+    /// No meeting can use the room in that slot
+    Room { room: i64, am: bool },
+}
+
+/// Load `owner=file.ics`, every VEVENT becomes busy time for the owner, in `zone`
+pub fn load(spec: &str, horizon: NaiveDateTime, zone: Tz) -> anyhow::Result<Vec<Busy>> {
+    let (owner, path) = spec
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected OWNER=file.ics got `{}`", spec))?;
+
+    let content = fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+    let calendar =
+        ics::parse_calendar(&content, zone).with_context(|| format!("parsing {}", path))?;
+    for warning in &calendar.warnings {
+        eprintln!("warning: {}: {}", path, warning);
+    }
+
+    Ok(expand(
+        &Owner::parse(owner.trim()),
+        &calendar.events,
+        horizon,
+    ))
+}
+
+/// Expand daily and weekly recurrences into one interval per occurrence before `horizon`
+pub fn expand(owner: &Owner, events: &[Event], horizon: NaiveDateTime) -> Vec<Busy> {
+    events
+        .iter()
+        .flat_map(|event| {
+            let length = event.end - event.start;
+            let starts = match &event.recurrence {
+                Some(recurrence) => recurrence.occurrences(event.start, horizon),
+                None => vec![event.start],
+            };
+
+            starts.into_iter().map(move |start| Busy {
+                owner: owner.clone(),
+                start,
+                end: start + length,
+            })
+        })
+        .collect()
+}
+
+/// Which slots are off limits, given who attends what and everyone's busy time
+pub fn blocked(meetings: &[Meeting], busy: &[Busy], config: &IcsConfig) -> Vec<Blocked> {
+    let mut blocked = Vec::new();
+
+    for am in [true, false] {
+        let (slot_start, slot_end) = config.slot_bounds(am);
+        let overlapping = busy
+            .iter()
+            .filter(|b| b.start < slot_end && slot_start < b.end);
+
+        for b in overlapping {
+            let newly_blocked: Vec<Blocked> = match &b.owner {
+                Owner::Room(room) => vec![Blocked::Room { room: *room, am }],
+                Owner::Participant(name) => meetings
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, m)| m.attendees.iter().any(|a| a.eq_ignore_ascii_case(name)))
+                    .map(|(meeting, _)| Blocked::Meeting { meeting, am })
+                    .collect(),
+            };
+
+            for block in newly_blocked {
+                if !blocked.contains(&block) {
+                    blocked.push(block);
+                }
+            }
+        }
+    }
+
+    blocked
+}
+
+#[cfg(test)]
+#[test]
+fn test_weekly_recurrence() {
+    let calendar = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        DTSTART:20250106T100000\r\n\
+        DURATION:PT1H30M\r\n\
+        RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;COUNT=3\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        DTSTART;VALUE=DATE:20250108\r\n\
+        RRULE:FREQ=WEEKLY;UNTIL=20250115\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    let events = ics::parse(calendar).unwrap();
+    let horizon = chrono::NaiveDate::from_ymd_opt(2025, 12, 31)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let busy = expand(&Owner::parse("Alice"), &events, horizon);
+
+    let starts: Vec<String> = busy
+        .iter()
+        .map(|b| b.start.format("%Y-%m-%d %H:%M").to_string())
+        .collect();
+    assert_eq!(
+        starts,
+        [
+            "2025-01-06 10:00",
+            "2025-01-09 10:00",
+            "2025-01-20 10:00",
+            "2025-01-08 00:00",
+            "2025-01-15 00:00",
+        ]
+    );
+
+    assert_eq!(busy[0].end - busy[0].start, chrono::Duration::minutes(90));
+    assert_eq!(busy[3].end - busy[3].start, chrono::Duration::days(1));
+    assert!(busy
+        .iter()
+        .all(|b| b.owner == Owner::Participant("Alice".into())));
+}
+
+#[cfg(test)]
+#[test]
+fn test_daily_recurrence() {
+    // Every other weekday from Thursday the 9th, a monthly event we can't expand
+    let calendar = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        DTSTART:20250109T090000\r\n\
+        DURATION:PT15M\r\n\
+        RRULE:FREQ=DAILY;INTERVAL=2;BYDAY=MO,TU,WE,TH,FR;COUNT=4\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        DTSTART:20250110T140000\r\n\
+        RRULE:FREQ=MONTHLY;BYMONTHDAY=10\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    let calendar = ics::parse_calendar(calendar, Tz::UTC).unwrap();
+    assert_eq!(calendar.warnings.len(), 1);
+    assert!(
+        calendar.warnings[0].starts_with("line 9: unsupported RRULE frequency `MONTHLY`"),
+        "{}",
+        calendar.warnings[0]
+    );
+
+    let horizon = chrono::NaiveDate::from_ymd_opt(2025, 12, 31)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let starts: Vec<String> = expand(&Owner::parse("Alice"), &calendar.events, horizon)
+        .iter()
+        .map(|b| b.start.format("%a %d %H:%M").to_string())
+        .collect();
+    assert_eq!(
+        starts,
+        [
+            "Thu 09 09:00",
+            "Mon 13 09:00",
+            "Wed 15 09:00",
+            "Fri 17 09:00",
+            "Fri 10 14:00",
+        ]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_zoned_times() {
+    // Exported in UTC and in New York time (UTC-5 in February), planned in Paris (UTC+1)
+    let calendar = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        DTSTART:20250324T080000Z\r\n\
+        DTEND:20250324T090000Z\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        DTSTART;TZID=America/New_York:20250203T090000\r\n\
+        DURATION:PT30M\r\n\
+        RRULE:FREQ=WEEKLY;UNTIL=20250211T140000Z\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+    let paris: Tz = "Europe/Paris".parse().unwrap();
+
+    let events = ics::parse_calendar(calendar, paris).unwrap().events;
+    let horizon = chrono::NaiveDate::from_ymd_opt(2025, 12, 31)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let busy = expand(&Owner::parse("Alice"), &events, horizon);
+    let times: Vec<String> = busy
+        .iter()
+        .map(|b| {
+            format!(
+                "{}-{}",
+                b.start.format("%Y-%m-%d %H:%M"),
+                b.end.format("%H:%M")
+            )
+        })
+        .collect();
+    assert_eq!(
+        times,
+        [
+            "2025-03-24 09:00-10:00",
+            "2025-02-03 15:00-15:30",
+            "2025-02-10 15:00-15:30",
+        ]
+    );
+
+    // Read as written without a zone to convert to
+    let events = ics::parse(calendar).unwrap();
+    assert_eq!(events[0].start.format("%H:%M").to_string(), "08:00");
+    assert_eq!(events[1].start.format("%H:%M").to_string(), "14:00");
+
+    let err = ics::parse_calendar(
+        "BEGIN:VEVENT\r\nDTSTART;TZID=Mars/Olympus:20250324T090000\r\nEND:VEVENT\r\n",
+        paris,
+    )
+    .unwrap_err();
+    assert!(format!("{:#}", err).contains("unknown TZID `Mars/Olympus`"));
+}
@@ -0,0 +1,250 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::bail;
+
+use super::{
+    model::ROOMS,
+    schedule::{room_name, Meeting, Solution},
+};
+
+/// Row label and cells, a cell holds zero or more entries
+pub type Row = (String, Vec<Vec<String>>);
+
+/// How `render` lays out the timeslot x room table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Aligned columns for the terminal
+    Text,
+    /// GitHub / wiki table
+    Markdown,
+    Html,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s.trim().to_lowercase().as_str() {
+            "text" => Format::Text,
+            "markdown" | "md" => Format::Markdown,
+            "html" => Format::Html,
+            other => bail!("unknown grid format `{}` (text, markdown, html)", other),
+        })
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Format::Text => "text",
+            Format::Markdown => "markdown",
+            Format::Html => "html",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Rows are AM/PM, columns R1..R3, each cell lists the meetings held there.
+/// With `names` the attendees follow each meeting, `M1 (alice, bob)`.
+pub fn render(meetings: &[Meeting], solution: &[Solution], format: Format, names: bool) -> String {
+    let header: Vec<String> = (1..=ROOMS).map(room_name).collect();
+
+    let rows: Vec<Row> = [("AM", true), ("PM", false)]
+        .into_iter()
+        .map(|(label, am)| {
+            let cells = (1..=ROOMS)
+                .map(|room| {
+                    meetings
+                        .iter()
+                        .zip(solution)
+                        .filter(|(_, s)| s.am == am && s.room == room)
+                        .map(|(meeting, _)| label_for(meeting, names))
+                        .collect()
+                })
+                .collect();
+            (label.to_string(), cells)
+        })
+        .collect();
+
+    table(format, &header, &rows)
+}
+
+/// Any labelled table, `header` names the columns after the label column
+pub fn table(format: Format, header: &[String], rows: &[Row]) -> String {
+    match format {
+        Format::Text => text(header, rows),
+        Format::Markdown => markdown(header, rows),
+        Format::Html => html(header, rows),
+    }
+}
+
+fn label_for(meeting: &Meeting, names: bool) -> String {
+    if names && !meeting.attendees.is_empty() {
+        format!("{} ({})", meeting.name, meeting.attendees.join(", "))
+    } else {
+        meeting.name.clone()
+    }
+}
+
+fn text(header: &[String], rows: &[Row]) -> String {
+    // Empty cells get a dash so the grid still reads as a grid
+    let lines: Vec<Vec<String>> = std::iter::once(
+        std::iter::once(String::new())
+            .chain(header.iter().cloned())
+            .collect(),
+    )
+    .chain(rows.iter().map(|(label, cells)| {
+        std::iter::once(label.clone())
+            .chain(cells.iter().map(|cell| {
+                if cell.is_empty() {
+                    "-".to_string()
+                } else {
+                    cell.join(", ")
+                }
+            }))
+            .collect()
+    }))
+    .collect();
+
+    let widths: Vec<usize> = (0..lines[0].len())
+        .map(|col| {
+            lines
+                .iter()
+                .map(|line| line[col].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut out = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let padded: Vec<String> = line
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:width$}", cell, width = width))
+            .collect();
+        out.push_str(padded.join(" | ").trim_end());
+        out.push('\n');
+
+        if i == 0 {
+            let rule: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+            out.push_str(&rule.join("-+-"));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// `&`, `<` and `>` as entities, for HTML and for markdown (whose cells hold `<br>`)
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// HTML escaped, and `|` no longer ends the cell
+fn escape_markdown(s: &str) -> String {
+    escape_html(s).replace('|', "\\|")
+}
+
+fn markdown(header: &[String], rows: &[Row]) -> String {
+    let header: Vec<String> = header.iter().map(|h| escape_markdown(h)).collect();
+    let mut out = format!("| | {} |\n", header.join(" | "));
+    out.push_str(&format!("|---|{}\n", "---|".repeat(header.len())));
+    for (label, cells) in rows {
+        let cells: Vec<String> = cells
+            .iter()
+            .map(|cell| {
+                if cell.is_empty() {
+                    " ".to_string()
+                } else {
+                    let content: Vec<String> = cell.iter().map(|m| escape_markdown(m)).collect();
+                    content.join("<br>")
+                }
+            })
+            .collect();
+        out.push_str(&format!(
+            "| **{}** | {} |\n",
+            escape_markdown(label),
+            cells.join(" | ")
+        ));
+    }
+    out
+}
+
+fn html(header: &[String], rows: &[Row]) -> String {
+    let mut out = String::from("<table>\n  <tr><th></th>");
+    for room in header {
+        out.push_str(&format!("<th>{}</th>", escape_html(room)));
+    }
+    out.push_str("</tr>\n");
+
+    for (label, cells) in rows {
+        out.push_str(&format!("  <tr><th>{}</th>", escape_html(label)));
+        for cell in cells {
+            let content: Vec<String> = cell.iter().map(|m| escape_html(m)).collect();
+            out.push_str(&format!("<td>{}</td>", content.join("<br>")));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+#[cfg(test)]
+#[test]
+fn test_render() {
+    let mut meetings = super::schedule::meetings(4, &["M1=alice,bob".to_string()]).unwrap();
+    meetings[3].name = "M4 <standup>".to_string();
+    let solution = super::schedule::parse_solutions("PM R1, AM R1, AM R2, PM R2").unwrap();
+
+    assert_eq!(
+        render(&meetings, &solution, Format::Text, false),
+        "   | R1 | R2           | R3\n\
+         ---+----+--------------+---\n\
+         AM | M2 | M3           | -\n\
+         PM | M1 | M4 <standup> | -\n"
+    );
+
+    assert_eq!(
+        render(&meetings, &solution, Format::Markdown, true),
+        "| | R1 | R2 | R3 |\n\
+         |---|---|---|---|\n\
+         | **AM** | M2 | M3 |   |\n\
+         | **PM** | M1 (alice, bob) | M4 &lt;standup&gt; |   |\n"
+    );
+
+    assert_eq!(
+        render(&meetings, &solution, Format::Html, false),
+        "<table>\n  \
+         <tr><th></th><th>R1</th><th>R2</th><th>R3</th></tr>\n  \
+         <tr><th>AM</th><td>M2</td><td>M3</td><td></td></tr>\n  \
+         <tr><th>PM</th><td>M1</td><td>M4 &lt;standup&gt;</td><td></td></tr>\n\
+         </table>\n"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_table_labels() {
+    // Headers and row labels come from users too (week's rooms and participants)
+    let header = ["Lab <3>".to_string(), "A|B".to_string()];
+    let rows: Vec<Row> = vec![(
+        "alice & bob".to_string(),
+        vec![vec!["x|y".to_string()], Vec::new()],
+    )];
+
+    assert_eq!(
+        table(Format::Markdown, &header, &rows),
+        "| | Lab &lt;3&gt; | A\\|B |\n\
+         |---|---|---|\n\
+         | **alice &amp; bob** | x\\|y |   |\n"
+    );
+    assert_eq!(
+        table(Format::Html, &header, &rows),
+        "<table>\n  \
+         <tr><th></th><th>Lab &lt;3&gt;</th><th>A|B</th></tr>\n  \
+         <tr><th>alice &amp; bob</th><td>x|y</td><td></td></tr>\n\
+         </table>\n"
+    );
+}
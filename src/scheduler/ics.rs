@@ -0,0 +1,586 @@
+use std::fmt;
+
+use anyhow::{anyhow, bail, Context};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+
+use super::schedule::{room_name, Meeting, Solution};
+
+const PRODID: &str = "-//rust-z3-examples//meeting2//EN";
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+type Params = Vec<(String, String)>;
+
+/// Where on the calendar the abstract AM/PM timeslots land
+#[derive(Debug, Clone)]
+pub struct IcsConfig {
+    pub base_date: NaiveDate,
+    pub am: (NaiveTime, NaiveTime),
+    pub pm: (NaiveTime, NaiveTime),
+    /// Attendees are exported as `mailto:<name>@<domain>`
+    pub domain: String,
+    /// Where the slots are wall clock times, imported UTC and TZID times are converted to it
+    pub zone: Tz,
+}
+
+impl Default for IcsConfig {
+    fn default() -> Self {
+        IcsConfig {
+            base_date: NaiveDate::from_ymd_opt(2025, 1, 6).unwrap(),
+            am: (hm(9, 0), hm(12, 0)),
+            pm: (hm(14, 0), hm(17, 0)),
+            domain: "example.com".to_string(),
+            zone: Tz::UTC,
+        }
+    }
+}
+
+impl IcsConfig {
+    /// Start and end of a timeslot on the base date
+    pub fn slot_bounds(&self, am: bool) -> (NaiveDateTime, NaiveDateTime) {
+        let (start, end) = if am { self.am } else { self.pm };
+        (self.base_date.and_time(start), self.base_date.and_time(end))
+    }
+}
+
+fn hm(hour: u32, min: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+}
+
+/// One VEVENT, times are floating (local wall clock, no TZID)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub uid: String,
+    pub summary: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub location: Option<String>,
+    pub attendees: Vec<String>,
+    pub recurrence: Option<Recurrence>,
+}
+
+/// How often a recurrence repeats, in units of INTERVAL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The subset of RRULE we understand: `FREQ=DAILY` or `FREQ=WEEKLY` with INTERVAL, BYDAY,
+/// COUNT and UNTIL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    /// Empty means the weekday of DTSTART when weekly, every day when daily
+    pub by_day: Vec<Weekday>,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDateTime>,
+}
+
+impl Recurrence {
+    fn parse(value: &str, zone: Tz) -> anyhow::Result<Self> {
+        let mut recurrence = Recurrence {
+            frequency: Frequency::Weekly,
+            interval: 1,
+            by_day: Vec::new(),
+            count: None,
+            until: None,
+        };
+        let mut frequency = None;
+
+        for part in value.split(';') {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| anyhow!("bad RRULE part `{}`", part))?;
+
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        _ => bail!("unsupported RRULE frequency `{}`", value),
+                    })
+                }
+                "INTERVAL" => recurrence.interval = value.parse()?,
+                "COUNT" => recurrence.count = Some(value.parse()?),
+                "UNTIL" => recurrence.until = Some(parse_date_time(&Params::new(), value, zone)?),
+                "BYDAY" => {
+                    recurrence.by_day = value
+                        .split(',')
+                        .map(|day| weekday(day).ok_or_else(|| anyhow!("bad BYDAY `{}`", day)))
+                        .collect::<anyhow::Result<_>>()?
+                }
+                "WKST" => {}
+                _ => bail!("unsupported RRULE part `{}`", part),
+            }
+        }
+
+        recurrence.frequency = frequency.ok_or_else(|| anyhow!("RRULE without FREQ"))?;
+        if recurrence.interval == 0 {
+            bail!("RRULE INTERVAL must be positive");
+        }
+
+        Ok(recurrence)
+    }
+
+    /// Start of every occurrence of an event starting at `start`, up to `horizon` (excluded)
+    pub fn occurrences(&self, start: NaiveDateTime, horizon: NaiveDateTime) -> Vec<NaiveDateTime> {
+        match self.frequency {
+            Frequency::Daily => self.daily(start, horizon),
+            Frequency::Weekly => self.weekly(start, horizon),
+        }
+    }
+
+    /// No occurrence from `occurrence` on, `found` being the number before it
+    fn ended(&self, occurrence: NaiveDateTime, horizon: NaiveDateTime, found: usize) -> bool {
+        occurrence >= horizon
+            || self.until.is_some_and(|until| occurrence > until)
+            || self.count.is_some_and(|count| found >= count as usize)
+    }
+
+    /// Every INTERVAL days, on the BYDAY days only if given
+    fn daily(&self, start: NaiveDateTime, horizon: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let mut occurrences = Vec::new();
+        let mut occurrence = start;
+
+        while !self.ended(occurrence, horizon, occurrences.len()) {
+            if self.by_day.is_empty() || self.by_day.contains(&occurrence.weekday()) {
+                occurrences.push(occurrence);
+            }
+            occurrence += Duration::days(self.interval.into());
+        }
+        occurrences
+    }
+
+    fn weekly(&self, start: NaiveDateTime, horizon: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let mut days = self.by_day.clone();
+        if days.is_empty() {
+            days.push(start.weekday());
+        }
+        days.sort_by_key(|day| day.num_days_from_monday());
+
+        // Weeks start on Monday (WKST default), INTERVAL skips whole weeks
+        let first_monday =
+            start.date() - Duration::days(start.weekday().num_days_from_monday().into());
+        let mut occurrences = Vec::new();
+        let mut week = 0;
+
+        loop {
+            let monday = first_monday + Duration::weeks(week * i64::from(self.interval));
+
+            for day in &days {
+                let occurrence = (monday + Duration::days(day.num_days_from_monday().into()))
+                    .and_time(start.time());
+
+                if occurrence < start {
+                    continue;
+                }
+                if self.ended(occurrence, horizon, occurrences.len()) {
+                    return occurrences;
+                }
+                occurrences.push(occurrence);
+            }
+
+            week += 1;
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FREQ={};INTERVAL={}", self.frequency, self.interval)?;
+        if !self.by_day.is_empty() {
+            let days: Vec<&str> = self.by_day.iter().map(|day| weekday_code(*day)).collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format(DATE_TIME_FORMAT))?;
+        }
+        Ok(())
+    }
+}
+
+const WEEKDAYS: [(Weekday, &str); 7] = [
+    (Weekday::Mon, "MO"),
+    (Weekday::Tue, "TU"),
+    (Weekday::Wed, "WE"),
+    (Weekday::Thu, "TH"),
+    (Weekday::Fri, "FR"),
+    (Weekday::Sat, "SA"),
+    (Weekday::Sun, "SU"),
+];
+
+fn weekday(code: &str) -> Option<Weekday> {
+    WEEKDAYS
+        .iter()
+        .find(|(_, c)| c.eq_ignore_ascii_case(code.trim()))
+        .map(|(day, _)| *day)
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    WEEKDAYS.iter().find(|(d, _)| *d == day).unwrap().1
+}
+
+/// Turn a solved schedule into calendar events, one per meeting
+pub fn events(meetings: &[Meeting], solutions: &[Solution], config: &IcsConfig) -> Vec<Event> {
+    meetings
+        .iter()
+        .zip(solutions.iter())
+        .map(|(meeting, solution)| {
+            let (start, end) = config.slot_bounds(solution.am);
+
+            Event {
+                uid: format!(
+                    "{}-{}@rust-z3-examples",
+                    meeting.name.to_lowercase(),
+                    config.base_date.format("%Y%m%d")
+                ),
+                summary: meeting.name.clone(),
+                start,
+                end,
+                location: Some(room_name(solution.room)),
+                attendees: meeting.attendees.clone(),
+                recurrence: None,
+            }
+        })
+        .collect()
+}
+
+/// Export a solved schedule as an RFC 5545 calendar
+pub fn export(meetings: &[Meeting], solutions: &[Solution], config: &IcsConfig) -> String {
+    write(&events(meetings, solutions, config), config)
+}
+
+/// Serialize events to a VCALENDAR (CRLF line endings, folded at 75 octets)
+pub fn write(events: &[Event], config: &IcsConfig) -> String {
+    // DTSTAMP is mandatory, pin it to the base date so exports are reproducible
+    let stamp = config.base_date.and_time(NaiveTime::MIN);
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{}Z", stamp.format(DATE_TIME_FORMAT)));
+        lines.push(format!("DTSTART:{}", event.start.format(DATE_TIME_FORMAT)));
+        lines.push(format!("DTEND:{}", event.end.format(DATE_TIME_FORMAT)));
+        if let Some(recurrence) = &event.recurrence {
+            lines.push(format!("RRULE:{}", recurrence));
+        }
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        if let Some(location) = &event.location {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+        for attendee in &event.attendees {
+            lines.push(format!(
+                "ATTENDEE;CN={}:mailto:{}@{}",
+                param_value(attendee),
+                attendee.to_lowercase().replace(' ', "."),
+                config.domain
+            ));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Parse every VEVENT of a calendar, properties we don't use are skipped.
+/// UTC and TZID times come out in UTC, `parse_calendar` converts them to another zone.
+pub fn parse(input: &str) -> anyhow::Result<Vec<Event>> {
+    parse_calendar(input, Tz::UTC).map(|calendar| calendar.events)
+}
+
+/// The events of a calendar, and what could not be read from them
+#[derive(Debug)]
+pub struct Calendar {
+    pub events: Vec<Event>,
+    /// Recurrences we don't understand, their events only keep the first occurrence
+    pub warnings: Vec<String>,
+}
+
+/// `parse`, times as wall clock times in `zone`, and a warning rather than an error for each
+/// recurrence we can't expand
+pub fn parse_calendar(input: &str, zone: Tz) -> anyhow::Result<Calendar> {
+    let mut events = Vec::new();
+    let mut warnings = Vec::new();
+    let mut current: Option<PartialEvent> = None;
+
+    for (number, line) in unfold(input).iter().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+
+        let (name, params, value) =
+            split_content_line(line).with_context(|| format!("line {}: `{}`", number + 1, line))?;
+
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value == "VEVENT" => current = Some(PartialEvent::default()),
+            ("END", Some(_)) if value == "VEVENT" => {
+                let event = current.take().unwrap();
+                events.push(
+                    event
+                        .finish()
+                        .with_context(|| format!("line {}", number + 1))?,
+                );
+            }
+            ("UID", Some(event)) => event.uid = Some(value),
+            ("SUMMARY", Some(event)) => event.summary = Some(unescape_text(&value)),
+            ("LOCATION", Some(event)) => event.location = Some(unescape_text(&value)),
+            ("DTSTART", Some(event)) => {
+                event.all_day = is_date(&params);
+                event.start = Some(parse_date_time(&params, &value, zone)?);
+            }
+            ("DTEND", Some(event)) => event.end = Some(parse_date_time(&params, &value, zone)?),
+            ("DURATION", Some(event)) => event.duration = Some(parse_duration(&value)?),
+            ("RRULE", Some(event)) => match Recurrence::parse(&value, zone) {
+                Ok(recurrence) => event.recurrence = Some(recurrence),
+                Err(err) => warnings.push(format!(
+                    "line {}: {:#}, only the first occurrence is kept",
+                    number + 1,
+                    err
+                )),
+            },
+            ("ATTENDEE", Some(event)) => event.attendees.push(attendee_name(&params, &value)),
+            _ => {}
+        }
+    }
+
+    if current.is_some() {
+        bail!("unterminated VEVENT");
+    }
+
+    Ok(Calendar { events, warnings })
+}
+
+#[derive(Default)]
+struct PartialEvent {
+    uid: Option<String>,
+    summary: Option<String>,
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+    duration: Option<Duration>,
+    all_day: bool,
+    location: Option<String>,
+    attendees: Vec<String>,
+    recurrence: Option<Recurrence>,
+}
+
+impl PartialEvent {
+    fn finish(self) -> anyhow::Result<Event> {
+        let start = self
+            .start
+            .ok_or_else(|| anyhow!("VEVENT without DTSTART"))?;
+
+        // RFC 5545 3.6.1: without DTEND or DURATION a date lasts one day,
+        // and a date-time ends when it starts
+        let end = match (self.end, self.duration) {
+            (Some(end), _) => end,
+            (None, Some(duration)) => start + duration,
+            (None, None) if self.all_day => start + Duration::days(1),
+            (None, None) => start,
+        };
+
+        Ok(Event {
+            uid: self.uid.unwrap_or_default(),
+            summary: self.summary.unwrap_or_default(),
+            start,
+            end,
+            location: self.location,
+            attendees: self.attendees,
+            recurrence: self.recurrence,
+        })
+    }
+}
+
+/// `NAME;PARAM=x;PARAM="y":value` -> (NAME, [(PARAM, x), (PARAM, y)], value)
+fn split_content_line(line: &str) -> anyhow::Result<(String, Params, String)> {
+    // The value starts at the first ':' outside of a quoted parameter
+    let mut in_quotes = false;
+    let colon = line
+        .char_indices()
+        .find(|&(_, c)| {
+            if c == '"' {
+                in_quotes = !in_quotes;
+            }
+            c == ':' && !in_quotes
+        })
+        .map(|(i, _)| i)
+        .ok_or_else(|| anyhow!("missing ':'"))?;
+
+    let mut parts = line[..colon].split(';');
+    let name = parts.next().unwrap_or_default().to_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.to_uppercase(), value.trim_matches('"').to_string()))
+        .collect();
+
+    Ok((name, params, line[colon + 1..].to_string()))
+}
+
+fn is_date(params: &Params) -> bool {
+    params.iter().any(|(k, v)| k == "VALUE" && v == "DATE")
+}
+
+/// Dates and floating times as written, UTC (`...Z`) and `TZID=` times converted to `zone`
+fn parse_date_time(params: &Params, value: &str, zone: Tz) -> anyhow::Result<NaiveDateTime> {
+    // UNTIL carries no VALUE parameter, a bare date is recognised by its length
+    if is_date(params) || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d")
+            .with_context(|| format!("bad DATE `{}`", value))?;
+        return Ok(date.and_time(NaiveTime::MIN));
+    }
+
+    let utc = value.ends_with('Z');
+    let time = NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), DATE_TIME_FORMAT)
+        .with_context(|| format!("bad DATE-TIME `{}`", value))?;
+
+    let tzid = params.iter().find(|(k, _)| k == "TZID").map(|(_, v)| v);
+    let zoned = match (utc, tzid) {
+        (true, _) => Tz::UTC.from_utc_datetime(&time),
+        (false, Some(tzid)) => {
+            let source: Tz = tzid
+                .parse()
+                .map_err(|_| anyhow!("unknown TZID `{}`, expected an IANA zone name", tzid))?;
+            source
+                .from_local_datetime(&time)
+                .earliest()
+                .ok_or_else(|| anyhow!("`{}` does not exist in {}", value, tzid))?
+        }
+        (false, None) => return Ok(time),
+    };
+    Ok(zoned.with_timezone(&zone).naive_local())
+}
+
+/// `P1W`, `P1D`, `PT1H30M`, ... (no months or years, their length is ambiguous)
+fn parse_duration(value: &str) -> anyhow::Result<Duration> {
+    let body = value
+        .strip_prefix('P')
+        .or_else(|| value.strip_prefix("+P"))
+        .ok_or_else(|| anyhow!("bad DURATION `{}`", value))?;
+
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+
+    for c in body.chars() {
+        match c {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let n: i64 = number
+                    .parse()
+                    .with_context(|| format!("bad DURATION `{}`", value))?;
+                number.clear();
+
+                duration += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(n),
+                    ('D', false) => Duration::days(n),
+                    ('H', true) => Duration::hours(n),
+                    ('M', true) => Duration::minutes(n),
+                    ('S', true) => Duration::seconds(n),
+                    _ => bail!("bad DURATION `{}`", value),
+                };
+            }
+        }
+    }
+
+    Ok(duration)
+}
+
+fn attendee_name(params: &Params, value: &str) -> String {
+    if let Some((_, cn)) = params.iter().find(|(k, _)| k == "CN") {
+        return cn.clone();
+    }
+
+    let address = value.strip_prefix("mailto:").unwrap_or(value);
+    address.split('@').next().unwrap_or(address).to_string()
+}
+
+/// Parameter values containing separators must be quoted (RFC 5545 3.1)
+fn param_value(value: &str) -> String {
+    if value.contains([':', ';', ',']) {
+        format!("\"{}\"", value.replace('"', ""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+/// Lines longer than 75 octets continue on the next line after a space
+fn fold(line: &str) -> String {
+    let mut result = String::new();
+    let mut width = 0;
+
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            result.push_str("\r\n ");
+            width = 1;
+        }
+        result.push(c);
+        width += c.len_utf8();
+    }
+
+    result.push_str("\r\n");
+    result
+}
+
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in input.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    lines
+}
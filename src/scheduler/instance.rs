@@ -0,0 +1,233 @@
+use std::fmt;
+
+use anyhow::bail;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use z3::ast::Bool;
+
+use super::model::Schedule;
+
+/// A random rule of a generated instance, meetings, slots and rooms are indices from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// The two meetings share attendees
+    Apart(usize, usize),
+    /// First meeting in an earlier slot than the second
+    Before(usize, usize),
+    AvoidSlot {
+        meeting: usize,
+        slot: usize,
+    },
+    AvoidRoom {
+        meeting: usize,
+        room: usize,
+    },
+    /// Like the puzzle's rule 2: if `meeting` is in `slot`, `other` is in `room`
+    IfThen {
+        meeting: usize,
+        slot: usize,
+        other: usize,
+        room: usize,
+    },
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Rule::Apart(i, j) => write!(f, "M{} and M{} in different slots", i + 1, j + 1),
+            Rule::Before(i, j) => write!(f, "M{} before M{}", i + 1, j + 1),
+            Rule::AvoidSlot { meeting, slot } => write!(f, "M{} not in S{}", meeting + 1, slot + 1),
+            Rule::AvoidRoom { meeting, room } => write!(f, "M{} not in R{}", meeting + 1, room + 1),
+            Rule::IfThen {
+                meeting,
+                slot,
+                other,
+                room,
+            } => write!(
+                f,
+                "if M{} is in S{} then M{} is in R{}",
+                meeting + 1,
+                slot + 1,
+                other + 1,
+                room + 1
+            ),
+        }
+    }
+}
+
+impl Rule {
+    /// Whether a (slot, room) per meeting satisfies the rule
+    pub fn holds(&self, assignment: &[(usize, usize)]) -> bool {
+        match *self {
+            Rule::Apart(i, j) => assignment[i].0 != assignment[j].0,
+            Rule::Before(i, j) => assignment[i].0 < assignment[j].0,
+            Rule::AvoidSlot { meeting, slot } => assignment[meeting].0 != slot,
+            Rule::AvoidRoom { meeting, room } => assignment[meeting].1 != room,
+            Rule::IfThen {
+                meeting,
+                slot,
+                other,
+                room,
+            } => assignment[meeting].0 != slot || assignment[other].1 == room,
+        }
+    }
+
+    pub fn constraint<'ctx>(&self, schedule: &Schedule<'ctx>) -> Bool<'ctx> {
+        match *self {
+            Rule::Apart(i, j) => schedule.same_slot(i, j).not(),
+            Rule::Before(i, j) => schedule.before(i, j),
+            Rule::AvoidSlot { meeting, slot } => schedule.in_slot(meeting, slot).not(),
+            Rule::AvoidRoom { meeting, room } => schedule.in_room(meeting, room as i64 + 1).not(),
+            Rule::IfThen {
+                meeting,
+                slot,
+                other,
+                room,
+            } => schedule
+                .in_slot(meeting, slot)
+                .implies(&schedule.in_room(other, room as i64 + 1)),
+        }
+    }
+}
+
+/// A generated problem: meetings on slots x rooms with random rules. Built around a
+/// hidden schedule every rule holds on, so it is always feasible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    pub seed: u64,
+    pub meetings: usize,
+    pub slots: usize,
+    pub rooms: usize,
+    pub rules: Vec<Rule>,
+    /// The planted (slot, room) of each meeting
+    pub hidden: Vec<(usize, usize)>,
+}
+
+impl Instance {
+    /// Same seed and sizes, same instance
+    pub fn generate(
+        seed: u64,
+        meetings: usize,
+        slots: usize,
+        rooms: usize,
+        rules: usize,
+    ) -> anyhow::Result<Self> {
+        if meetings > slots * rooms {
+            bail!(
+                "{} meetings cannot fit in {} slots x {} rooms",
+                meetings,
+                slots,
+                rooms
+            );
+        }
+        if meetings < 2 && rules > 0 {
+            bail!("rules need at least two meetings");
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut cells: Vec<(usize, usize)> = (0..slots)
+            .flat_map(|slot| (0..rooms).map(move |room| (slot, room)))
+            .collect();
+        cells.shuffle(&mut rng);
+        let hidden: Vec<(usize, usize)> = cells.into_iter().take(meetings).collect();
+
+        // Draw rules until enough of them hold on the hidden schedule
+        let mut picked: Vec<Rule> = Vec::new();
+        let mut attempts = 0;
+        while picked.len() < rules {
+            attempts += 1;
+            if attempts > rules * 100 {
+                bail!("could not draw {} distinct rules", rules);
+            }
+
+            let (i, j) = (rng.gen_range(0..meetings), rng.gen_range(0..meetings));
+            if i == j {
+                continue;
+            }
+            let rule = match rng.gen_range(0..5) {
+                0 => Rule::Apart(i.min(j), i.max(j)),
+                1 => Rule::Before(i, j),
+                2 => Rule::AvoidSlot {
+                    meeting: i,
+                    slot: rng.gen_range(0..slots),
+                },
+                3 => Rule::AvoidRoom {
+                    meeting: i,
+                    room: rng.gen_range(0..rooms),
+                },
+                _ => Rule::IfThen {
+                    meeting: i,
+                    slot: rng.gen_range(0..slots),
+                    other: j,
+                    room: rng.gen_range(0..rooms),
+                },
+            };
+            if rule.holds(&hidden) && !picked.contains(&rule) {
+                picked.push(rule);
+            }
+        }
+
+        Ok(Instance {
+            seed,
+            meetings,
+            slots,
+            rooms,
+            rules: picked,
+            hidden,
+        })
+    }
+
+    /// Domain (bounds, no double booked room) plus the rules
+    pub fn constraints<'ctx>(&self, schedule: &Schedule<'ctx>) -> Vec<Bool<'ctx>> {
+        let mut constraints = schedule.domain();
+        for rule in &self.rules {
+            constraints.push(rule.constraint(schedule));
+        }
+        constraints
+    }
+
+    /// Independent check of a (slot, room) per meeting
+    pub fn check(&self, assignment: &[(usize, usize)]) -> bool {
+        let in_range = assignment
+            .iter()
+            .all(|&(slot, room)| slot < self.slots && room < self.rooms);
+        let no_clash = (0..assignment.len())
+            .all(|i| (i + 1..assignment.len()).all(|j| assignment[i] != assignment[j]));
+
+        assignment.len() == self.meetings
+            && in_range
+            && no_clash
+            && self.rules.iter().all(|rule| rule.holds(assignment))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_generate() {
+    let instance = Instance::generate(7, 30, 6, 6, 60).unwrap();
+    assert_eq!(instance, Instance::generate(7, 30, 6, 6, 60).unwrap());
+    assert_ne!(instance, Instance::generate(8, 30, 6, 6, 60).unwrap());
+
+    assert_eq!(instance.rules.len(), 60);
+    assert!(instance.check(&instance.hidden));
+
+    // Every kind of rule shows up
+    let kinds: Vec<std::mem::Discriminant<Rule>> =
+        instance.rules.iter().map(std::mem::discriminant).collect();
+    for rule in [
+        Rule::Apart(0, 1),
+        Rule::Before(0, 1),
+        Rule::AvoidSlot {
+            meeting: 0,
+            slot: 0,
+        },
+        Rule::AvoidRoom {
+            meeting: 0,
+            room: 0,
+        },
+    ] {
+        assert!(kinds.contains(&std::mem::discriminant(&rule)), "{}", rule);
+    }
+
+    assert!(Instance::generate(7, 37, 6, 6, 0).is_err());
+}
@@ -0,0 +1,16 @@
+// Shared between the scheduler binaries, not every bin uses every helper
+#![allow(dead_code)]
+
+pub mod args;
+pub mod busy;
+pub mod grid;
+pub mod ics;
+pub mod instance;
+pub mod model;
+pub mod objectives;
+pub mod preferences;
+pub mod schedule;
+pub mod travel;
+pub mod validate;
+pub mod week;
+pub mod zones;
@@ -0,0 +1,199 @@
+use z3::{
+    ast::{self, Ast, Bool, Dynamic, Int},
+    Context, Model,
+};
+
+use super::{busy::Blocked, schedule::Solution};
+use crate::domain::{Domain, Encoding};
+
+/// Rooms are R1..R3
+pub const ROOMS: i64 = 3;
+
+/// AM and PM
+pub const SLOTS: usize = 2;
+
+/// z3 variables of the meeting puzzle, meeting i is (slot[i], room[i]).
+/// Slot 0 is AM, room value r - 1 is Rr, whatever the encoding.
+pub struct Schedule<'ctx> {
+    ctx: &'ctx Context,
+    pub slots: Domain<'ctx>,
+    pub rooms: Domain<'ctx>,
+    pub slot: Vec<Dynamic<'ctx>>,
+    pub room: Vec<Dynamic<'ctx>>,
+}
+
+impl<'ctx> Schedule<'ctx> {
+    /// The puzzle's AM/PM and R1..R3
+    pub fn new(ctx: &'ctx Context, meetings: usize, encoding: Encoding) -> Self {
+        Self::sized(ctx, meetings, SLOTS, ROOMS as usize, encoding)
+    }
+
+    /// Any number of slots and rooms, for generated instances
+    pub fn sized(
+        ctx: &'ctx Context,
+        meetings: usize,
+        slots: usize,
+        rooms: usize,
+        encoding: Encoding,
+    ) -> Self {
+        let slots = Domain::new(ctx, encoding, "Slot", slots);
+        let rooms = Domain::new(ctx, encoding, "Room", rooms);
+        Schedule {
+            ctx,
+            slot: (1..=meetings)
+                .map(|i| slots.var(&format!("m{}_slot", i)))
+                .collect(),
+            room: (1..=meetings)
+                .map(|i| rooms.var(&format!("m{}_room", i)))
+                .collect(),
+            slots,
+            rooms,
+        }
+    }
+
+    fn int(&self, value: i64) -> Int<'ctx> {
+        ast::Int::from_i64(self.ctx, value)
+    }
+
+    /// Number of meetings
+    pub fn meetings(&self) -> usize {
+        self.slot.len()
+    }
+
+    /// Meeting is in the morning
+    pub fn am(&self, meeting: usize) -> Bool<'ctx> {
+        self.in_slot(meeting, 0)
+    }
+
+    pub fn in_slot(&self, meeting: usize, slot: usize) -> Bool<'ctx> {
+        self.slots.is(&self.slot[meeting], slot)
+    }
+
+    /// Meeting is in room Rn (n from 1)
+    pub fn in_room(&self, meeting: usize, room: i64) -> Bool<'ctx> {
+        match usize::try_from(room - 1) {
+            Ok(value) => self.rooms.is(&self.room[meeting], value),
+            Err(_) => Bool::from_bool(self.ctx, false),
+        }
+    }
+
+    pub fn same_room(&self, i: usize, j: usize) -> Bool<'ctx> {
+        self.room[i]._eq(&self.room[j])
+    }
+
+    pub fn same_slot(&self, i: usize, j: usize) -> Bool<'ctx> {
+        self.slot[i]._eq(&self.slot[j])
+    }
+
+    /// Meeting i is in an earlier slot than meeting j
+    pub fn before(&self, i: usize, j: usize) -> Bool<'ctx> {
+        self.slots.lt(&self.slot[i], &self.slot[j])
+    }
+
+    /// Slot index as an integer, 0 for AM and 1 for PM
+    pub fn slot_index(&self, meeting: usize) -> Int<'ctx> {
+        (1..self.slots.size).fold(self.int(0), |index, slot| {
+            self.in_slot(meeting, slot)
+                .ite(&self.int(slot as i64), &index)
+        })
+    }
+
+    /// Slots and rooms in range and no two meetings sharing a room at the same time
+    pub fn domain(&self) -> Vec<Bool<'ctx>> {
+        let mut constraints = Vec::new();
+
+        // Rooms need to be in [1, 3] (nothing to do for enumerations)
+        for (slot, room) in self.slot.iter().zip(&self.room) {
+            constraints.extend(self.slots.bounds(slot));
+            constraints.extend(self.rooms.bounds(room));
+        }
+
+        // No duplicates (cannot have same meeting AND same room)
+        for i in 0..self.meetings() {
+            for j in i + 1..self.meetings() {
+                constraints.push(!(self.same_room(i, j) & self.same_slot(i, j)));
+            }
+        }
+
+        constraints
+    }
+
+    /// Existing commitments (imported calendars)
+    pub fn blocked(&self, blocked: &[Blocked]) -> Vec<Bool<'ctx>> {
+        let mut constraints = Vec::new();
+
+        for block in blocked {
+            match *block {
+                Blocked::Meeting { meeting, am } => {
+                    constraints.push(self.am(meeting)._eq(&Bool::from_bool(self.ctx, am)).not());
+                }
+                Blocked::Room { room, am } => {
+                    for i in 0..self.meetings() {
+                        constraints.push(
+                            !(self.am(i)._eq(&Bool::from_bool(self.ctx, am))
+                                & self.in_room(i, room)),
+                        );
+                    }
+                }
+            }
+        }
+
+        constraints
+    }
+
+    /// For each meeting: it is not where it was in `previous`
+    pub fn moved(&self, previous: &[Solution]) -> Vec<Bool<'ctx>> {
+        previous
+            .iter()
+            .enumerate()
+            .map(|(i, before)| {
+                !(self.am(i)._eq(&Bool::from_bool(self.ctx, before.am))
+                    & self.in_room(i, before.room))
+            })
+            .collect()
+    }
+
+    /// The eight rules of the puzzle, rule n at index n - 1 (needs M1..M4)
+    pub fn rules(&self) -> Vec<Bool<'ctx>> {
+        let [m1_am, m2_am, m3_am, m4_am] = [self.am(0), self.am(1), self.am(2), self.am(3)];
+
+        vec![
+            // 1.	M1 and M2 cannot be held in the same timeslot.
+            ast::Bool::xor(&m1_am, &m2_am),
+            // 2.	If M3 is scheduled in the morning, then M4 cannot be held in R1 or R3.
+            m3_am.implies(&self.in_room(3, 2)),
+            // 3.	If M2 is scheduled in R2, then M1 must not be in the afternoon.
+            self.in_room(1, 2).implies(&m1_am),
+            // 4.   At least two of the meetings (out of M1, M2, M3, M4) must be in the afternoon timeslot.
+            ast::Int::add(
+                self.ctx,
+                &[
+                    &m1_am.ite(&self.int(0), &self.int(1)),
+                    &m2_am.ite(&self.int(0), &self.int(1)),
+                    &m3_am.ite(&self.int(0), &self.int(1)),
+                    &m4_am.ite(&self.int(0), &self.int(1)),
+                ],
+            )
+            .ge(&self.int(2)),
+            // 5.	M1 must not be in the same room as M3.
+            self.same_room(0, 2).not(),
+            // 6.	M4 must be either in a different timeslot than M1 or, if in the same timeslot, it must be in R2.
+            self.same_slot(3, 0).implies(&self.in_room(3, 2)),
+            // 7.	If M3 is in R3, then M2 cannot be in the same room as M4.
+            self.in_room(2, 3).implies(&self.same_room(1, 3).not()),
+            // 8.	If M1 is scheduled in the morning, then M3 must not be scheduled in the afternoon.
+            m1_am.implies(&m3_am),
+        ]
+    }
+
+    pub fn decode(&self, model: &Model<'ctx>) -> Vec<Solution> {
+        self.slot
+            .iter()
+            .zip(&self.room)
+            .map(|(slot, room)| Solution {
+                am: self.slots.decode(model, slot) == 0,
+                room: self.rooms.decode(model, room) as i64 + 1,
+            })
+            .collect()
+    }
+}
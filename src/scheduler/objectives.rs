@@ -0,0 +1,285 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::bail;
+use z3::ast::{self, Ast, Bool, Int};
+
+use super::{
+    model::Schedule,
+    schedule::{Meeting, Solution},
+};
+
+/// Something to minimize, several are optimized lexicographically in the given order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// Number of distinct rooms in use
+    Rooms,
+    /// Number of meetings held in the afternoon
+    Afternoon,
+    /// Index of the last timeslot in use, the day ends as early as possible
+    Early,
+    /// Empty slots between a participant's first and last meeting, summed over participants
+    Gaps,
+    /// Most afternoon meetings any single participant has
+    Fair,
+    /// Afternoon meetings of the most loaded participant minus those of the least loaded one
+    Spread,
+}
+
+impl FromStr for Objective {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s.trim() {
+            "rooms" => Objective::Rooms,
+            "afternoon" => Objective::Afternoon,
+            "early" => Objective::Early,
+            "gaps" => Objective::Gaps,
+            "fair" => Objective::Fair,
+            "spread" => Objective::Spread,
+            other => bail!(
+                "unknown objective `{}` (rooms, afternoon, early, gaps, fair, spread)",
+                other
+            ),
+        })
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Objective::Rooms => "rooms",
+            Objective::Afternoon => "afternoon",
+            Objective::Early => "early",
+            Objective::Gaps => "gaps",
+            Objective::Fair => "fair",
+            Objective::Spread => "spread",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// `rooms,afternoon` -> [Rooms, Afternoon]
+pub fn parse_list(list: &str) -> anyhow::Result<Vec<Objective>> {
+    list.split(',').map(str::parse).collect()
+}
+
+impl Objective {
+    /// The integer term to minimize
+    pub fn term<'ctx>(&self, schedule: &Schedule<'ctx>, meetings: &[Meeting]) -> Int<'ctx> {
+        let ctx = schedule.slot[0].get_ctx();
+        let meeting_indices = 0..schedule.meetings();
+
+        let terms: Vec<Int<'ctx>> = match self {
+            Objective::Rooms => (1..=schedule.rooms.size as i64)
+                .map(|room| {
+                    let used: Vec<Bool> = meeting_indices
+                        .clone()
+                        .map(|i| schedule.in_room(i, room))
+                        .collect();
+                    count(ctx, &[any(ctx, &used)])
+                })
+                .collect(),
+            Objective::Afternoon => meeting_indices
+                .map(|i| count(ctx, &[schedule.am(i).not()]))
+                .collect(),
+            Objective::Early => {
+                // The last slot in use is the highest slot index any meeting has
+                let last = meeting_indices.fold(ast::Int::from_i64(ctx, 0), |last, i| {
+                    let slot = schedule.slot_index(i);
+                    slot.gt(&last).ite(&slot, &last)
+                });
+                vec![last]
+            }
+            Objective::Gaps => people(meetings)
+                .into_iter()
+                .map(|person| idle_slots(schedule, &attended(meetings, person)))
+                .collect(),
+            Objective::Fair | Objective::Spread => {
+                let loads: Vec<Int<'ctx>> = people(meetings)
+                    .into_iter()
+                    .map(|person| {
+                        let afternoons: Vec<Bool> = attended(meetings, person)
+                            .into_iter()
+                            .map(|i| schedule.am(i).not())
+                            .collect();
+                        count(ctx, &afternoons)
+                    })
+                    .collect();
+                let highest = extreme(ctx, &loads, |a, b| a.gt(b));
+                match self {
+                    Objective::Fair => vec![highest],
+                    _ => vec![highest - extreme(ctx, &loads, |a, b| a.lt(b))],
+                }
+            }
+        };
+
+        sum(ctx, &terms)
+    }
+}
+
+/// How many meetings one participant has, and how many of them in the afternoon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Load {
+    pub person: String,
+    pub meetings: usize,
+    pub afternoon: usize,
+}
+
+impl fmt::Display for Load {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} meeting(s), {} in the afternoon",
+            self.person, self.meetings, self.afternoon
+        )
+    }
+}
+
+/// Load of every participant of a schedule, in order of first appearance
+pub fn loads(meetings: &[Meeting], solution: &[Solution]) -> Vec<Load> {
+    people(meetings)
+        .into_iter()
+        .map(|person| {
+            let theirs = attended(meetings, person);
+            Load {
+                person: person.clone(),
+                meetings: theirs.len(),
+                afternoon: theirs.iter().filter(|&&i| !solution[i].am).count(),
+            }
+        })
+        .collect()
+}
+
+/// Everybody attending at least one meeting, once
+fn people(meetings: &[Meeting]) -> Vec<&String> {
+    let mut people: Vec<&String> = Vec::new();
+    for person in meetings.iter().flat_map(|m| &m.attendees) {
+        if !people.contains(&person) {
+            people.push(person);
+        }
+    }
+    people
+}
+
+/// Indices of the meetings `person` attends
+fn attended(meetings: &[Meeting], person: &str) -> Vec<usize> {
+    meetings
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.attendees.iter().any(|a| a == person))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Largest (or smallest, depending on `better`) of the values, 0 when there are none
+fn extreme<'ctx>(
+    ctx: &'ctx z3::Context,
+    values: &[Int<'ctx>],
+    better: impl Fn(&Int<'ctx>, &Int<'ctx>) -> Bool<'ctx>,
+) -> Int<'ctx> {
+    match values.split_first() {
+        Some((first, rest)) => rest.iter().fold(first.clone(), |best, value| {
+            better(value, &best).ite(value, &best)
+        }),
+        None => ast::Int::from_i64(ctx, 0),
+    }
+}
+
+/// Slots in which the person is free, but has meetings both before and after
+fn idle_slots<'ctx>(schedule: &Schedule<'ctx>, meetings: &[usize]) -> Int<'ctx> {
+    let ctx = schedule.slot[0].get_ctx();
+
+    let occupied: Vec<Bool<'ctx>> = (0..schedule.slots.size)
+        .map(|slot| {
+            let here: Vec<Bool> = meetings
+                .iter()
+                .map(|&i| schedule.in_slot(i, slot))
+                .collect();
+            any(ctx, &here)
+        })
+        .collect();
+
+    let idle: Vec<Bool<'ctx>> = (0..occupied.len())
+        .map(|slot| {
+            let before = any(ctx, &occupied[..slot]);
+            let after = any(ctx, &occupied[slot + 1..]);
+            ast::Bool::and(ctx, &[&before, &after, &occupied[slot].not()])
+        })
+        .collect();
+
+    count(ctx, &idle)
+}
+
+fn any<'ctx>(ctx: &'ctx z3::Context, values: &[Bool<'ctx>]) -> Bool<'ctx> {
+    let refs: Vec<&Bool> = values.iter().collect();
+    ast::Bool::or(ctx, &refs)
+}
+
+/// Number of true values
+fn count<'ctx>(ctx: &'ctx z3::Context, values: &[Bool<'ctx>]) -> Int<'ctx> {
+    let ones: Vec<Int<'ctx>> = values
+        .iter()
+        .map(|value| value.ite(&ast::Int::from_i64(ctx, 1), &ast::Int::from_i64(ctx, 0)))
+        .collect();
+    sum(ctx, &ones)
+}
+
+fn sum<'ctx>(ctx: &'ctx z3::Context, values: &[Int<'ctx>]) -> Int<'ctx> {
+    if values.is_empty() {
+        return ast::Int::from_i64(ctx, 0);
+    }
+    let refs: Vec<&Int> = values.iter().collect();
+    ast::Int::add(ctx, &refs)
+}
+
+#[cfg(test)]
+#[test]
+fn test_objectives() {
+    use crate::domain::Encoding;
+    use z3::{Config, Context, Optimize, SatResult};
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let schedule = Schedule::sized(&ctx, 2, 4, 4, Encoding::Int);
+    let mut meetings = vec![Meeting::new("M1"), Meeting::new("M2")];
+    for meeting in &mut meetings {
+        meeting.attendees.push("ann".to_string());
+    }
+
+    let gaps = Objective::Gaps.term(&schedule, &meetings);
+    let rooms = Objective::Rooms.term(&schedule, &meetings);
+
+    // ann starts in slot 0 and would rather meet again in slot 3, idle in slots 1 and 2,
+    // [gaps, rooms, M2 in slot 1] at the optimum
+    let optimum = |objectives: &[Objective]| {
+        let optimize = Optimize::new(&ctx);
+        for constraint in schedule.domain() {
+            optimize.assert(&constraint);
+        }
+        optimize.assert(&schedule.in_slot(0, 0));
+        optimize.assert(&schedule.in_slot(1, 0).not());
+        optimize.assert(&schedule.in_room(0, 4));
+        optimize.assert(&schedule.in_room(1, 3));
+        for objective in objectives {
+            optimize.minimize(&objective.term(&schedule, &meetings));
+        }
+        optimize.assert_soft(&schedule.in_slot(1, 3), 1, None);
+
+        assert_eq!(optimize.check(&[]), SatResult::Sat);
+        let model = optimize.get_model().unwrap();
+        (
+            model.eval(&gaps, true).unwrap().as_i64().unwrap(),
+            model.eval(&rooms, true).unwrap().as_i64().unwrap(),
+            model
+                .eval(&schedule.in_slot(1, 1), true)
+                .unwrap()
+                .as_bool()
+                .unwrap(),
+        )
+    };
+
+    // Rooms past R3 count too
+    assert_eq!(optimum(&[]), (2, 2, false));
+    // Fewer gaps comes first: the next slot, whatever ann would rather
+    assert_eq!(optimum(&[Objective::Gaps]), (0, 2, true));
+}
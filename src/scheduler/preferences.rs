@@ -0,0 +1,145 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use chrono::Weekday;
+use z3::ast::{self, Ast, Bool};
+
+use super::{model::Schedule, schedule::room_name};
+
+/// Where a meeting would like to be (or not to be)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Place {
+    Slot { am: bool },
+    Room(i64),
+}
+
+/// A soft rule: violating it costs `weight`, see `FromStr` for the syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preference {
+    /// Meeting index (M1 is 0), `None` for every meeting
+    pub meeting: Option<usize>,
+    pub place: Place,
+    pub avoid: bool,
+    /// Only applies when the schedule falls on that day
+    pub day: Option<Weekday>,
+    pub weight: u64,
+}
+
+/// `M2 R1 5` (M2 would like R1, weight 5), `all !PM Fri 3` (avoid Friday afternoon),
+/// the weight defaults to 1
+impl FromStr for Preference {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut tokens = s.split_whitespace();
+
+        let meeting = match tokens.next() {
+            Some(who) if who.eq_ignore_ascii_case("all") => None,
+            Some(who) => Some(meeting_index(who)?),
+            None => bail!("empty preference"),
+        };
+
+        let place = tokens
+            .next()
+            .ok_or_else(|| anyhow!("preference `{}` has no AM/PM/room", s))?;
+        let (avoid, place) = match place.strip_prefix('!') {
+            Some(place) => (true, place),
+            None => (false, place),
+        };
+        let place = match place.to_uppercase().as_str() {
+            "AM" => Place::Slot { am: true },
+            "PM" => Place::Slot { am: false },
+            room => Place::Room(
+                room.strip_prefix('R')
+                    .and_then(|r| r.parse().ok())
+                    .ok_or_else(|| anyhow!("expected AM, PM or Rn, got `{}`", place))?,
+            ),
+        };
+
+        let mut day = None;
+        let mut weight = 1;
+        for token in tokens {
+            if let Ok(w) = token.parse() {
+                weight = w;
+            } else {
+                day = Some(
+                    token
+                        .parse::<Weekday>()
+                        .map_err(|_| anyhow!("expected a weekday or a weight, got `{}`", token))?,
+                );
+            }
+        }
+
+        Ok(Preference {
+            meeting,
+            place,
+            avoid,
+            day,
+            weight,
+        })
+    }
+}
+
+fn meeting_index(name: &str) -> anyhow::Result<usize> {
+    name.strip_prefix(['M', 'm'])
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|&n| n >= 1)
+        .map(|n| n - 1)
+        .ok_or_else(|| anyhow!("expected a meeting (M1, M2, ...) or `all`, got `{}`", name))
+}
+
+impl fmt::Display for Preference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.meeting {
+            Some(i) => write!(f, "M{}", i + 1)?,
+            None => write!(f, "all meetings")?,
+        }
+        write!(f, "{}", if self.avoid { " avoid " } else { " in " })?;
+        match self.place {
+            Place::Slot { am } => write!(f, "{}", if am { "AM" } else { "PM" })?,
+            Place::Room(room) => write!(f, "{}", room_name(room))?,
+        }
+        if let Some(day) = self.day {
+            write!(f, " on {}", day)?;
+        }
+        write!(f, " (weight {})", self.weight)
+    }
+}
+
+impl Preference {
+    /// Holds when the preference is honored, on a schedule taking place on `weekday`
+    pub fn constraint<'ctx>(
+        &self,
+        schedule: &Schedule<'ctx>,
+        weekday: Option<Weekday>,
+    ) -> Bool<'ctx> {
+        let ctx = schedule.slot[0].get_ctx();
+
+        if self.day.is_some() && self.day != weekday {
+            return ast::Bool::from_bool(ctx, true);
+        }
+
+        let meetings: Vec<usize> = match self.meeting {
+            Some(i) => vec![i],
+            None => (0..schedule.meetings()).collect(),
+        };
+
+        let honored: Vec<Bool<'ctx>> = meetings
+            .into_iter()
+            .map(|i| {
+                let there = match self.place {
+                    Place::Slot { am } => schedule.am(i)._eq(&ast::Bool::from_bool(ctx, am)),
+                    Place::Room(room) => schedule.in_room(i, room),
+                };
+                if self.avoid {
+                    there.not()
+                } else {
+                    there
+                }
+            })
+            .collect();
+
+        let refs: Vec<&Bool> = honored.iter().collect();
+        ast::Bool::and(ctx, &refs)
+    }
+}
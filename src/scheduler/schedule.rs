@@ -0,0 +1,102 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail};
+
+/// Assignment of a single meeting, as read from the z3 model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Solution {
+    pub am: bool,
+    pub room: i64,
+}
+
+/// `AM R1`, as printed by `Display`
+impl FromStr for Solution {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut tokens = s.split_whitespace();
+
+        let am = match tokens.next().map(str::to_uppercase).as_deref() {
+            Some("AM") => true,
+            Some("PM") => false,
+            _ => bail!("expected `AM Rn` or `PM Rn`, got `{}`", s),
+        };
+        let room = tokens
+            .next()
+            .and_then(|room| room.strip_prefix(['R', 'r']))
+            .and_then(|room| room.parse().ok())
+            .ok_or_else(|| anyhow!("expected `AM Rn` or `PM Rn`, got `{}`", s))?;
+
+        Ok(Solution { am, room })
+    }
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let slot = if self.am { "AM" } else { "PM" };
+        write!(f, "{} {}", slot, room_name(self.room))
+    }
+}
+
+/// `PM R1, AM R1, AM R2, PM R2` -> one `Solution` per meeting, M1 first
+pub fn parse_solutions(list: &str) -> anyhow::Result<Vec<Solution>> {
+    list.split(',').map(str::parse).collect()
+}
+
+/// Meetings whose slot or room differs between two schedules
+pub fn changed(previous: &[Solution], current: &[Solution]) -> Vec<usize> {
+    previous
+        .iter()
+        .zip(current)
+        .enumerate()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// A meeting to place, M1..Mn follow the index in the `Solution` list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Meeting {
+    pub name: String,
+    pub attendees: Vec<String>,
+}
+
+impl Meeting {
+    pub fn new(name: &str) -> Self {
+        Meeting {
+            name: name.to_string(),
+            attendees: Vec::new(),
+        }
+    }
+}
+
+/// Build M1..M`count`, then fill attendees from `M2=alice,bob` style specs
+pub fn meetings(count: usize, attendee_specs: &[String]) -> anyhow::Result<Vec<Meeting>> {
+    let mut meetings: Vec<Meeting> = (1..=count)
+        .map(|i| Meeting::new(&format!("M{}", i)))
+        .collect();
+
+    for spec in attendee_specs {
+        let (name, attendees) = spec
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected MEETING=name,name got `{}`", spec))?;
+
+        let Some(meeting) = meetings.iter_mut().find(|m| m.name == name.trim()) else {
+            bail!("unknown meeting `{}`", name);
+        };
+
+        meeting.attendees = attendees
+            .split(',')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(String::from)
+            .collect();
+    }
+
+    Ok(meetings)
+}
+
+/// Room number to its display name (1 -> "R1")
+pub fn room_name(room: i64) -> String {
+    format!("R{}", room)
+}
@@ -0,0 +1,184 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail};
+
+use super::schedule::room_name;
+
+/// A named building and the rooms in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Building {
+    pub name: String,
+    pub rooms: Vec<i64>,
+}
+
+/// `North=R1,R2`
+impl FromStr for Building {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let usage = || anyhow!("expected `<building>=R1,R2,...`, got `{}`", s);
+
+        let (name, rooms) = s.split_once('=').ok_or_else(usage)?;
+        let rooms = rooms
+            .split(',')
+            .map(|room| {
+                room.trim()
+                    .strip_prefix(['R', 'r'])
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(usage)
+            })
+            .collect::<anyhow::Result<Vec<i64>>>()?;
+
+        let name = name.trim();
+        if name.is_empty() || name.contains('-') {
+            bail!("building names cannot be empty or contain `-`, got `{}`", s);
+        }
+
+        Ok(Building {
+            name: name.to_string(),
+            rooms,
+        })
+    }
+}
+
+impl fmt::Display for Building {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rooms: Vec<String> = self.rooms.iter().map(|&r| room_name(r)).collect();
+        write!(f, "{}: {}", self.name, rooms.join(", "))
+    }
+}
+
+/// Walking time between two buildings, the same both ways
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Travel {
+    pub from: String,
+    pub to: String,
+    pub minutes: i64,
+}
+
+/// `North-South=15`
+impl FromStr for Travel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let usage = || anyhow!("expected `<building>-<building>=<minutes>`, got `{}`", s);
+
+        let (pair, minutes) = s.split_once('=').ok_or_else(usage)?;
+        let (from, to) = pair.split_once('-').ok_or_else(usage)?;
+        let minutes = minutes.trim().parse().map_err(|_| usage())?;
+        if minutes < 0 {
+            bail!("negative travel time in `{}`", s);
+        }
+
+        Ok(Travel {
+            from: from.trim().to_string(),
+            to: to.trim().to_string(),
+            minutes,
+        })
+    }
+}
+
+/// Where the rooms are. Rooms in no building, and buildings without a travel
+/// time between them, are treated as next door.
+#[derive(Debug, Clone, Default)]
+pub struct Campus {
+    pub buildings: Vec<Building>,
+    pub travel: Vec<Travel>,
+}
+
+impl Campus {
+    pub fn new(buildings: Vec<Building>, travel: Vec<Travel>) -> anyhow::Result<Self> {
+        for (i, building) in buildings.iter().enumerate() {
+            for room in &building.rooms {
+                if let Some(other) = buildings[..i].iter().find(|b| b.rooms.contains(room)) {
+                    bail!(
+                        "{} is in both {} and {}",
+                        room_name(*room),
+                        other.name,
+                        building.name
+                    );
+                }
+            }
+        }
+        for t in &travel {
+            for name in [&t.from, &t.to] {
+                if !buildings.iter().any(|b| &b.name == name) {
+                    bail!("travel time to unknown building `{}`", name);
+                }
+            }
+        }
+
+        Ok(Campus { buildings, travel })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buildings.is_empty()
+    }
+
+    /// Every room of the campus exists in a plan with `rooms` rooms
+    pub fn check(&self, rooms: i64) -> anyhow::Result<()> {
+        for building in &self.buildings {
+            if let Some(room) = building.rooms.iter().find(|&&r| r < 1 || r > rooms) {
+                bail!(
+                    "{} of {} does not exist, there are {} rooms",
+                    room_name(*room),
+                    building.name,
+                    rooms
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn building_of(&self, room: i64) -> Option<&str> {
+        self.buildings
+            .iter()
+            .find(|b| b.rooms.contains(&room))
+            .map(|b| b.name.as_str())
+    }
+
+    /// Minutes to get from one room to the other
+    pub fn minutes(&self, from: i64, to: i64) -> i64 {
+        match (self.building_of(from), self.building_of(to)) {
+            (Some(a), Some(b)) if a != b => self
+                .travel
+                .iter()
+                .find(|t| (t.from == a && t.to == b) || (t.from == b && t.to == a))
+                .map_or(0, |t| t.minutes),
+            _ => 0,
+        }
+    }
+
+    /// Longest walk on the campus, nothing to enforce between slots further apart
+    pub fn longest(&self) -> i64 {
+        self.travel.iter().map(|t| t.minutes).max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_campus() {
+    let buildings: Vec<Building> = ["North=R1,R2", "South=R3"]
+        .iter()
+        .map(|b| b.parse().unwrap())
+        .collect();
+    let travel: Vec<Travel> = vec!["North-South=15".parse().unwrap()];
+    let campus = Campus::new(buildings.clone(), travel.clone()).unwrap();
+
+    assert_eq!(buildings[0].rooms, [1, 2]);
+    assert_eq!(campus.building_of(3), Some("South"));
+    assert_eq!(campus.building_of(4), None);
+    assert_eq!(campus.minutes(1, 2), 0);
+    assert_eq!(campus.minutes(1, 3), 15);
+    assert_eq!(campus.minutes(3, 2), 15);
+    assert_eq!(campus.minutes(3, 4), 0);
+    assert_eq!(campus.longest(), 15);
+
+    assert!(campus.check(3).is_ok());
+    assert!(campus.check(2).is_err());
+    assert!("North=1,2".parse::<Building>().is_err());
+    assert!("North-South=soon".parse::<Travel>().is_err());
+    assert!(Campus::new(buildings.clone(), vec!["North-East=5".parse().unwrap()]).is_err());
+    let twice = vec![buildings[0].clone(), "South=R2".parse().unwrap()];
+    assert!(Campus::new(twice, travel).is_err());
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use anyhow::bail;
+
+use super::{
+    model::ROOMS,
+    schedule::{room_name, Solution},
+};
+
+/// The rules as stated, rule n at index n - 1
+pub const RULES: [&str; 8] = [
+    "M1 and M2 cannot be held in the same timeslot.",
+    "If M3 is scheduled in the morning, then M4 cannot be held in R1 or R3.",
+    "If M2 is scheduled in R2, then M1 must not be in the afternoon.",
+    "At least two of the meetings (out of M1, M2, M3, M4) must be in the afternoon timeslot.",
+    "M1 must not be in the same room as M3.",
+    "M4 must be either in a different timeslot than M1 or, if in the same timeslot, it must be in R2.",
+    "If M3 is in R3, then M2 cannot be in the same room as M4.",
+    "If M1 is scheduled in the morning, then M3 must not be scheduled in the afternoon.",
+];
+
+/// Something wrong with a concrete schedule, meetings are indices (M1 is 0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// Not one of R1..R3
+    Room { meeting: usize, room: i64 },
+    /// Same room at the same time
+    Clash { first: usize, second: usize },
+    /// One of the numbered rules
+    Rule(usize),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::Room { meeting, room } => write!(
+                f,
+                "M{} is in {}, rooms are R1..R{}",
+                meeting + 1,
+                room_name(room),
+                ROOMS
+            ),
+            Violation::Clash { first, second } => {
+                write!(
+                    f,
+                    "M{} and M{} are in the same room at the same time",
+                    first + 1,
+                    second + 1
+                )
+            }
+            Violation::Rule(n) => write!(f, "rule {}: {}", n, RULES[n - 1]),
+        }
+    }
+}
+
+/// Check a schedule against the rules as written, without z3.
+/// Needs exactly M1..M4.
+pub fn violations(schedule: &[Solution]) -> anyhow::Result<Vec<Violation>> {
+    if schedule.len() != 4 {
+        bail!(
+            "the puzzle has four meetings, got {} `AM Rn`/`PM Rn`",
+            schedule.len()
+        );
+    }
+
+    let mut violations = Vec::new();
+
+    for (meeting, solution) in schedule.iter().enumerate() {
+        if !(1..=ROOMS).contains(&solution.room) {
+            violations.push(Violation::Room {
+                meeting,
+                room: solution.room,
+            });
+        }
+    }
+
+    for first in 0..schedule.len() {
+        for second in first + 1..schedule.len() {
+            if schedule[first] == schedule[second] {
+                violations.push(Violation::Clash { first, second });
+            }
+        }
+    }
+
+    let [m1, m2, m3, m4] = [schedule[0], schedule[1], schedule[2], schedule[3]];
+    let afternoon = schedule.iter().filter(|m| !m.am).count();
+
+    let rules = [
+        m1.am != m2.am,
+        !m3.am || (m4.room != 1 && m4.room != 3),
+        m2.room != 2 || m1.am,
+        afternoon >= 2,
+        m1.room != m3.room,
+        m4.am != m1.am || m4.room == 2,
+        m3.room != 3 || m2.room != m4.room,
+        !m1.am || m3.am,
+    ];
+
+    for (i, holds) in rules.iter().enumerate() {
+        if !holds {
+            violations.push(Violation::Rule(i + 1));
+        }
+    }
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+#[test]
+fn test_violations() {
+    let check = |list: &str| -> Vec<Violation> {
+        violations(&super::schedule::parse_solutions(list).unwrap()).unwrap()
+    };
+
+    assert_eq!(check("PM R1, AM R1, AM R2, PM R2"), []);
+
+    // M3 in the morning with M4 in R3, M3 in R3 with M2 and M4 sharing a room
+    assert_eq!(
+        check("AM R1, PM R3, AM R3, PM R3"),
+        [
+            Violation::Clash {
+                first: 1,
+                second: 3
+            },
+            Violation::Rule(2),
+            Violation::Rule(7),
+        ]
+    );
+
+    // Three meetings in the morning, M1 and M3 share R1 and M4 is nowhere
+    assert_eq!(
+        check("AM R1, AM R2, PM R1, AM R4"),
+        [
+            Violation::Room {
+                meeting: 3,
+                room: 4
+            },
+            Violation::Rule(1),
+            Violation::Rule(4),
+            Violation::Rule(5),
+            Violation::Rule(6),
+            Violation::Rule(8),
+        ]
+    );
+
+    assert_eq!(
+        Violation::Rule(5).to_string(),
+        "rule 5: M1 must not be in the same room as M3."
+    );
+
+    // Too few or too many meetings is an error, not a panic
+    let parse = |list: &str| super::schedule::parse_solutions(list).unwrap();
+    let short = violations(&parse("PM R1, AM R1")).unwrap_err();
+    assert_eq!(
+        short.to_string(),
+        "the puzzle has four meetings, got 2 `AM Rn`/`PM Rn`"
+    );
+    assert!(violations(&parse("PM R1, AM R1, AM R2, PM R2, AM R3")).is_err());
+}
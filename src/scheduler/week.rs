@@ -0,0 +1,497 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use z3::{
+    ast::{self, Ast, Bool, Int},
+    Context, Model,
+};
+
+use super::{
+    grid::{self, Format},
+    model::ROOMS,
+    schedule::room_name,
+    travel::Campus,
+};
+
+/// The days and timeslots a weekly plan can use
+#[derive(Debug, Clone)]
+pub struct Week {
+    pub days: Vec<Weekday>,
+    /// Start of each timeslot, every day has the same ones
+    pub slots: Vec<NaiveTime>,
+    pub rooms: i64,
+    /// Any date of the planned week, needed to place slots on the calendar (DST)
+    pub date: NaiveDate,
+    /// Zone of the common grid, slot times are wall clock times there
+    pub zone: Tz,
+    /// Length of a meeting
+    pub length: Duration,
+}
+
+impl Default for Week {
+    fn default() -> Self {
+        Week {
+            days: parse_days("Mon-Fri").unwrap(),
+            slots: parse_slots("09:00,14:00").unwrap(),
+            rooms: ROOMS,
+            date: NaiveDate::from_ymd_opt(2025, 1, 6).unwrap(),
+            zone: Tz::UTC,
+            length: Duration::hours(1),
+        }
+    }
+}
+
+impl Week {
+    pub fn day_index(&self, day: Weekday) -> Option<usize> {
+        self.days.iter().position(|&d| d == day)
+    }
+
+    pub fn slot_label(&self, slot: usize) -> String {
+        self.slots[slot].format("%H:%M").to_string()
+    }
+
+    pub fn date_of(&self, day: Weekday) -> NaiveDate {
+        let monday = self.date - Duration::days(self.date.weekday().num_days_from_monday() as i64);
+        monday + Duration::days(day.num_days_from_monday() as i64)
+    }
+
+    /// When a slot starts, `None` if the grid zone skips that wall clock time (DST)
+    pub fn start_of(&self, day: Weekday, slot: usize) -> Option<DateTime<Tz>> {
+        self.zone
+            .from_local_datetime(&self.date_of(day).and_time(self.slots[slot]))
+            .earliest()
+    }
+}
+
+/// `Mon,Wed,Fri` or a range, `Mon-Fri`
+pub fn parse_days(list: &str) -> anyhow::Result<Vec<Weekday>> {
+    let day = |s: &str| {
+        s.trim()
+            .parse::<Weekday>()
+            .map_err(|_| anyhow!("expected a weekday, got `{}`", s.trim()))
+    };
+
+    if let Some((first, last)) = list.split_once('-') {
+        let (first, last) = (day(first)?, day(last)?);
+        let mut days = vec![first];
+        while *days.last().unwrap() != last {
+            days.push(days.last().unwrap().succ());
+        }
+        return Ok(days);
+    }
+
+    list.split(',').map(day).collect()
+}
+
+/// `09:00,14:00`, one start time per slot
+pub fn parse_slots(list: &str) -> anyhow::Result<Vec<NaiveTime>> {
+    let mut slots = list
+        .split(',')
+        .map(|s| {
+            NaiveTime::parse_from_str(s.trim(), "%H:%M")
+                .map_err(|_| anyhow!("expected a time like 09:00, got `{}`", s.trim()))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    slots.sort();
+    slots.dedup();
+    Ok(slots)
+}
+
+/// A meeting of the weekly plan, see `FromStr` for the syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Series {
+    pub name: String,
+    /// Held on each of these days, always at the same time and in the same room.
+    /// Empty for a one-off meeting, on whichever day fits.
+    pub days: Vec<Weekday>,
+    pub attendees: Vec<String>,
+}
+
+/// `Standup every Tue and Thu with alice, bob`, `Review once with carol`
+impl FromStr for Series {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (head, attendees) = match s.split_once(" with ") {
+            Some((head, attendees)) => (head, attendees),
+            None => (s, ""),
+        };
+        let attendees = attendees
+            .split(',')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let (name, days) = if let Some((name, days)) = head.split_once(" every ") {
+            (name, parse_days(&days.replace(" and ", ","))?)
+        } else if let Some(name) = head.trim_end().strip_suffix(" once") {
+            (name, Vec::new())
+        } else {
+            bail!(
+                "expected `<name> every <days> [with <attendees>]` or `<name> once [with <attendees>]`, got `{}`",
+                s
+            );
+        };
+
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("meeting without a name in `{}`", s);
+        }
+
+        let mut unique = Vec::new();
+        for day in days {
+            if !unique.contains(&day) {
+                unique.push(day);
+            }
+        }
+
+        Ok(Series {
+            name: name.to_string(),
+            days: unique,
+            attendees,
+        })
+    }
+}
+
+impl fmt::Display for Series {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.days.is_empty() {
+            write!(f, " once")?;
+        } else {
+            let days: Vec<String> = self.days.iter().map(|d| d.to_string()).collect();
+            write!(f, " every {}", days.join(", "))?;
+        }
+        if !self.attendees.is_empty() {
+            write!(f, " with {}", self.attendees.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// At most `max` meetings a day, for the whole plan or for one person
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limit {
+    pub person: Option<String>,
+    pub max: usize,
+}
+
+/// `3` (whole plan) or `alice=2`
+impl FromStr for Limit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (person, max) = match s.split_once('=') {
+            Some((person, max)) => (Some(person.trim().to_string()), max),
+            None => (None, s),
+        };
+        let max = max
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("expected `<max>` or `<person>=<max>`, got `{}`", s))?;
+        Ok(Limit { person, max })
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(person) = &self.person {
+            write!(f, "{}: ", person)?;
+        }
+        write!(f, "at most {} meeting(s) a day", self.max)
+    }
+}
+
+/// `person` is not available on day index `day`, slot `slot`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Away {
+    pub person: String,
+    pub day: usize,
+    pub slot: usize,
+}
+
+/// One occurrence of a series, as placed by the solver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Booking {
+    /// Index into the series list
+    pub series: usize,
+    pub day: Weekday,
+    /// Index into `Week::slots`
+    pub slot: usize,
+    pub room: i64,
+}
+
+/// z3 variables of a weekly plan, occurrence i is (day[i], slot[i], room[i]),
+/// day and slot being indices into `Week`
+pub struct Plan<'ctx> {
+    ctx: &'ctx Context,
+    /// Series of each occurrence
+    pub series: Vec<usize>,
+    pub day: Vec<Int<'ctx>>,
+    pub slot: Vec<Int<'ctx>>,
+    pub room: Vec<Int<'ctx>>,
+}
+
+impl<'ctx> Plan<'ctx> {
+    /// One occurrence per listed day, a single one for a one-off meeting
+    pub fn new(ctx: &'ctx Context, series: &[Series]) -> Self {
+        let mut plan = Plan {
+            ctx,
+            series: Vec::new(),
+            day: Vec::new(),
+            slot: Vec::new(),
+            room: Vec::new(),
+        };
+
+        for (s, meeting) in series.iter().enumerate() {
+            for k in 0..meeting.days.len().max(1) {
+                let var = |what: &str| ast::Int::new_const(ctx, format!("s{}_{}_{}", s, k, what));
+                plan.series.push(s);
+                plan.day.push(var("day"));
+                plan.slot.push(var("slot"));
+                plan.room.push(var("room"));
+            }
+        }
+
+        plan
+    }
+
+    fn int(&self, value: i64) -> Int<'ctx> {
+        ast::Int::from_i64(self.ctx, value)
+    }
+
+    /// People who cannot attend at some day and slot
+    pub fn away(&self, series: &[Series], away: &[Away]) -> Vec<Bool<'ctx>> {
+        let mut constraints = Vec::new();
+
+        for absence in away {
+            for (i, &s) in self.series.iter().enumerate() {
+                if series[s].attendees.contains(&absence.person) {
+                    constraints.push(
+                        !(self.day[i]._eq(&self.int(absence.day as i64))
+                            & self.slot[i]._eq(&self.int(absence.slot as i64))),
+                    );
+                }
+            }
+        }
+
+        constraints
+    }
+
+    /// Occurrences i and j are on the same day in the same slot
+    pub fn together(&self, i: usize, j: usize) -> Bool<'ctx> {
+        self.day[i]._eq(&self.day[j]) & self.slot[i]._eq(&self.slot[j])
+    }
+
+    pub fn constraints(
+        &self,
+        week: &Week,
+        series: &[Series],
+        limits: &[Limit],
+    ) -> anyhow::Result<Vec<Bool<'ctx>>> {
+        let mut constraints = Vec::new();
+        let n = self.series.len();
+
+        for i in 0..n {
+            constraints.push(self.day[i].ge(&self.int(0)));
+            constraints.push(self.day[i].lt(&self.int(week.days.len() as i64)));
+            constraints.push(self.slot[i].ge(&self.int(0)));
+            constraints.push(self.slot[i].lt(&self.int(week.slots.len() as i64)));
+            constraints.push(self.room[i].ge(&self.int(1)));
+            constraints.push(self.room[i].le(&self.int(week.rooms)));
+        }
+
+        // Recurring meetings: pinned to their days, same time and room on every occurrence
+        for (s, meeting) in series.iter().enumerate() {
+            let occurrences: Vec<usize> = (0..n).filter(|&i| self.series[i] == s).collect();
+            for (&i, &day) in occurrences.iter().zip(&meeting.days) {
+                let index = week.day_index(day).ok_or_else(|| {
+                    anyhow!(
+                        "`{}` is on {}, which is not part of the week",
+                        meeting.name,
+                        day
+                    )
+                })?;
+                constraints.push(self.day[i]._eq(&self.int(index as i64)));
+            }
+            for &i in &occurrences[1..] {
+                constraints.push(self.slot[i]._eq(&self.slot[occurrences[0]]));
+                constraints.push(self.room[i]._eq(&self.room[occurrences[0]]));
+            }
+        }
+
+        // No room used twice at once, nobody in two meetings at once
+        for i in 0..n {
+            for j in i + 1..n {
+                constraints.push(!(self.together(i, j) & self.room[i]._eq(&self.room[j])));
+
+                let (a, b) = (&series[self.series[i]], &series[self.series[j]]);
+                if a.attendees
+                    .iter()
+                    .any(|person| b.attendees.contains(person))
+                {
+                    constraints.push(self.together(i, j).not());
+                }
+            }
+        }
+
+        for limit in limits {
+            let counted: Vec<usize> = match &limit.person {
+                Some(person) => {
+                    if !series.iter().any(|s| s.attendees.contains(person)) {
+                        bail!("limit for {}, who attends no meeting", person);
+                    }
+                    (0..n)
+                        .filter(|&i| series[self.series[i]].attendees.contains(person))
+                        .collect()
+                }
+                None => (0..n).collect(),
+            };
+
+            for day in 0..week.days.len() {
+                let ones: Vec<Int> = counted
+                    .iter()
+                    .map(|&i| {
+                        self.day[i]
+                            ._eq(&self.int(day as i64))
+                            .ite(&self.int(1), &self.int(0))
+                    })
+                    .collect();
+                if ones.is_empty() {
+                    continue;
+                }
+                let refs: Vec<&Int> = ones.iter().collect();
+                constraints.push(ast::Int::add(self.ctx, &refs).le(&self.int(limit.max as i64)));
+            }
+        }
+
+        Ok(constraints)
+    }
+
+    /// Someone attending two meetings the same day has time to walk from one building to the other
+    pub fn travel(&self, week: &Week, series: &[Series], campus: &Campus) -> Vec<Bool<'ctx>> {
+        let mut constraints = Vec::new();
+        let n = self.series.len();
+
+        // Minutes from the room of occurrence i to the room of occurrence j
+        let walk = |i: usize, j: usize| {
+            let mut minutes = self.int(0);
+            for from in 1..=week.rooms {
+                for to in 1..=week.rooms {
+                    let m = campus.minutes(from, to);
+                    if m > 0 {
+                        minutes = (self.room[i]._eq(&self.int(from))
+                            & self.room[j]._eq(&self.int(to)))
+                        .ite(&self.int(m), &minutes);
+                    }
+                }
+            }
+            minutes
+        };
+
+        for i in 0..n {
+            for j in 0..n {
+                let (a, b) = (&series[self.series[i]], &series[self.series[j]]);
+                if i == j
+                    || !a
+                        .attendees
+                        .iter()
+                        .any(|person| b.attendees.contains(person))
+                {
+                    continue;
+                }
+
+                // i in an earlier slot than j, the same day
+                for (first, &start) in week.slots.iter().enumerate() {
+                    for (second, &next) in week.slots.iter().enumerate() {
+                        let gap = (next - start - week.length).num_minutes();
+                        if next <= start || gap >= campus.longest() {
+                            continue;
+                        }
+                        let consecutive = self.day[i]._eq(&self.day[j])
+                            & self.slot[i]._eq(&self.int(first as i64))
+                            & self.slot[j]._eq(&self.int(second as i64));
+                        constraints.push(consecutive.implies(&walk(i, j).le(&self.int(gap))));
+                    }
+                }
+            }
+        }
+
+        constraints
+    }
+
+    pub fn decode(&self, model: &Model<'ctx>, week: &Week) -> Vec<Booking> {
+        let value = |var: &Int<'ctx>| model.eval(var, true).unwrap().as_i64().unwrap();
+
+        (0..self.series.len())
+            .map(|i| Booking {
+                series: self.series[i],
+                day: week.days[value(&self.day[i]) as usize],
+                slot: value(&self.slot[i]) as usize,
+                room: value(&self.room[i]),
+            })
+            .collect()
+    }
+}
+
+/// Rows are timeslots, columns days, each cell lists `Standup (R1)`
+pub fn render(week: &Week, series: &[Series], bookings: &[Booking], format: Format) -> String {
+    let header: Vec<String> = week.days.iter().map(|d| d.to_string()).collect();
+
+    let rows: Vec<grid::Row> = (0..week.slots.len())
+        .map(|slot| {
+            let cells = week
+                .days
+                .iter()
+                .map(|&day| {
+                    bookings
+                        .iter()
+                        .filter(|b| b.day == day && b.slot == slot)
+                        .map(|b| format!("{} ({})", series[b.series].name, room_name(b.room)))
+                        .collect()
+                })
+                .collect();
+            (week.slot_label(slot), cells)
+        })
+        .collect();
+
+    grid::table(format, &header, &rows)
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_series() {
+    let standup: Series = "Standup every Tue and Thu with alice, bob".parse().unwrap();
+    assert_eq!(standup.name, "Standup");
+    assert_eq!(standup.days, [Weekday::Tue, Weekday::Thu]);
+    assert_eq!(standup.attendees, ["alice", "bob"]);
+    assert_eq!(
+        standup.to_string(),
+        "Standup every Tue, Thu with alice, bob"
+    );
+
+    let sync: Series = "Team sync every Mon, Wed and Friday".parse().unwrap();
+    assert_eq!(sync.name, "Team sync");
+    assert_eq!(sync.days, [Weekday::Mon, Weekday::Wed, Weekday::Fri]);
+
+    let review: Series = "Review once with carol".parse().unwrap();
+    assert!(review.days.is_empty());
+
+    assert!("Standup on Tuesdays".parse::<Series>().is_err());
+    assert!("Standup every Tue and Caturday".parse::<Series>().is_err());
+
+    assert_eq!(
+        parse_days("Mon-Wed").unwrap(),
+        [Weekday::Mon, Weekday::Tue, Weekday::Wed]
+    );
+    assert_eq!(
+        "alice=2".parse::<Limit>().unwrap(),
+        Limit {
+            person: Some("alice".to_string()),
+            max: 2
+        }
+    );
+}
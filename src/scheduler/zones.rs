@@ -0,0 +1,214 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Weekday};
+use chrono_tz::Tz;
+
+use super::{
+    schedule::room_name,
+    travel::Campus,
+    week::{self, Away, Booking, Series, Week},
+};
+
+/// Where someone works from, and when (local wall clock)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Participant {
+    pub name: String,
+    pub zone: Tz,
+    pub hours: (NaiveTime, NaiveTime),
+    pub days: Vec<Weekday>,
+}
+
+/// `alice=Europe/Paris 09:00-17:00`, optionally followed by the local working days
+/// (`Mon-Thu`, `Sun-Thu`), Monday to Friday by default
+impl FromStr for Participant {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let usage = || {
+            anyhow!(
+                "expected `<name>=<zone> <HH:MM>-<HH:MM> [days]`, got `{}`",
+                s
+            )
+        };
+
+        let (name, rest) = s.split_once('=').ok_or_else(usage)?;
+        let mut tokens = rest.split_whitespace();
+
+        let zone = tokens.next().ok_or_else(usage)?;
+        let zone = zone
+            .parse::<Tz>()
+            .map_err(|_| anyhow!("unknown time zone `{}` (try Europe/Paris, UTC)", zone))?;
+
+        let (start, end) = tokens
+            .next()
+            .and_then(|hours| hours.split_once('-'))
+            .ok_or_else(usage)?;
+        let time = |t: &str| NaiveTime::parse_from_str(t, "%H:%M").map_err(|_| usage());
+        let hours = (time(start)?, time(end)?);
+
+        let days = match tokens.next() {
+            Some(days) => week::parse_days(days)?,
+            None => week::parse_days("Mon-Fri")?,
+        };
+
+        Ok(Participant {
+            name: name.trim().to_string(),
+            zone,
+            hours,
+            days,
+        })
+    }
+}
+
+impl fmt::Display for Participant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, {}-{})",
+            self.name,
+            self.zone,
+            self.hours.0.format("%H:%M"),
+            self.hours.1.format("%H:%M")
+        )
+    }
+}
+
+impl Participant {
+    /// Someone without declared hours, always available, seen from `zone`
+    pub fn anywhere(name: &str, zone: Tz) -> Self {
+        Participant {
+            name: name.to_string(),
+            zone,
+            hours: (NaiveTime::MIN, NaiveTime::from_hms_opt(23, 59, 59).unwrap()),
+            days: week::parse_days("Mon-Sun").unwrap(),
+        }
+    }
+
+    /// A meeting from `start` to `start + length` is inside the local working hours
+    pub fn available(&self, start: DateTime<Tz>, length: Duration) -> bool {
+        let local_start = start.with_timezone(&self.zone);
+        let local_end = (start + length).with_timezone(&self.zone);
+
+        local_start.date_naive() == local_end.date_naive()
+            && self.days.contains(&local_start.weekday())
+            && local_start.time() >= self.hours.0
+            && local_end.time() <= self.hours.1
+    }
+}
+
+/// Every (person, day, slot) of the grid outside that person's working hours.
+/// Slots the grid zone skips (spring forward) are off for everybody.
+pub fn away(week: &Week, participants: &[Participant]) -> Vec<Away> {
+    let mut away = Vec::new();
+
+    for participant in participants {
+        for (day, &weekday) in week.days.iter().enumerate() {
+            for slot in 0..week.slots.len() {
+                let fits = week
+                    .start_of(weekday, slot)
+                    .is_some_and(|start| participant.available(start, week.length));
+                if !fits {
+                    away.push(Away {
+                        person: participant.name.clone(),
+                        day,
+                        slot,
+                    });
+                }
+            }
+        }
+    }
+
+    away
+}
+
+/// The participant's meetings in their own time, `Tue 15:00-16:00 CET Standup (R1)`,
+/// with the building when known and the walk between back-to-back meetings in different buildings
+pub fn agenda(
+    week: &Week,
+    series: &[Series],
+    bookings: &[Booking],
+    participant: &Participant,
+    campus: &Campus,
+) -> Vec<String> {
+    let mut entries: Vec<(DateTime<Tz>, &Booking)> = bookings
+        .iter()
+        .filter(|b| series[b.series].attendees.contains(&participant.name))
+        .filter_map(|b| {
+            let start = week
+                .start_of(b.day, b.slot)?
+                .with_timezone(&participant.zone);
+            Some((start, b))
+        })
+        .collect();
+    entries.sort_by_key(|(start, _)| *start);
+
+    let mut lines = Vec::new();
+    for (k, &(start, b)) in entries.iter().enumerate() {
+        if let Some(&(previous_start, previous)) = k.checked_sub(1).map(|p| &entries[p]) {
+            let minutes = campus.minutes(previous.room, b.room);
+            if previous_start.date_naive() == start.date_naive() && minutes > 0 {
+                lines.push(format!(
+                    "  {} min walk from {} to {}",
+                    minutes,
+                    campus.building_of(previous.room).unwrap(),
+                    campus.building_of(b.room).unwrap()
+                ));
+            }
+        }
+
+        let end = start + week.length;
+        let room = match campus.building_of(b.room) {
+            Some(building) => format!("{}, {}", room_name(b.room), building),
+            None => room_name(b.room),
+        };
+        lines.push(format!(
+            "{} {}-{} {} {} ({})",
+            start.format("%a"),
+            start.format("%H:%M"),
+            end.format("%H:%M"),
+            start.format("%Z"),
+            series[b.series].name,
+            room
+        ));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+#[test]
+fn test_working_hours() {
+    use chrono::{NaiveDate, TimeZone};
+
+    let paris: Participant = "alice=Europe/Paris 09:00-17:00".parse().unwrap();
+    let new_york: Participant = "bob=America/New_York 09:00-17:00 Mon-Thu".parse().unwrap();
+    assert_eq!(new_york.days.len(), 4);
+    assert!("carol=Mars/Olympus 09:00-17:00"
+        .parse::<Participant>()
+        .is_err());
+
+    // UTC hours both can attend a one hour meeting, on a given date
+    let overlap = |date: NaiveDate| -> Vec<u32> {
+        (0..24)
+            .filter(|&hour| {
+                let start = Tz::UTC.from_utc_datetime(&date.and_hms_opt(hour, 0, 0).unwrap());
+                paris.available(start, Duration::hours(1))
+                    && new_york.available(start, Duration::hours(1))
+            })
+            .collect()
+    };
+
+    // Winter: Paris is UTC+1, New York UTC-5
+    assert_eq!(
+        overlap(NaiveDate::from_ymd_opt(2025, 1, 7).unwrap()),
+        [14, 15]
+    );
+    // The US switch to summer time three weeks before Europe
+    assert_eq!(
+        overlap(NaiveDate::from_ymd_opt(2025, 3, 11).unwrap()),
+        [13, 14, 15]
+    );
+    // Friday is not a working day in New York for bob
+    assert_eq!(overlap(NaiveDate::from_ymd_opt(2025, 1, 10).unwrap()), []);
+}
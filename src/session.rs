@@ -0,0 +1,317 @@
+mod domain;
+mod scheduler;
+
+use std::{
+    env,
+    io::{self, BufRead, Write},
+};
+
+use anyhow::{anyhow, bail};
+use domain::Encoding;
+use scheduler::{
+    args::flag_value, model::Schedule, preferences::Preference, schedule::Solution, validate::RULES,
+};
+use z3::{
+    ast::{self, Bool},
+    Config, Context, SatResult, Solver,
+};
+
+const HELP: &str = "Commands:
+  add <rule>          hard rule, same syntax as --require: `add M2 !AM`, `add all !R3`
+  drop <n>            stop enforcing rule n (puzzle rules are 1 to 8)
+  pin <Mn> <AM|PM|Rn>...  keep a meeting where it is told: `pin M2 AM R1`
+  undo                take back the last add, drop or pin
+  rules               list the rules, dropped ones included
+  show                check again and print the schedule
+  help, quit";
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    println!(
+        "Interactive session on the four meeting puzzle (see meeting2).
+        Rules can be added, dropped and undone, meetings pinned, and the schedule is checked again after every step.
+        A single solver is kept for the whole session: every step is a push, undo is a pop."
+    );
+    println!("{}", HELP);
+
+    // [--encoding int|bv|enum]
+    let encoding = match flag_value(&args, "--encoding") {
+        Some(encoding) => encoding.parse()?,
+        None => Encoding::default(),
+    };
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let mut session = Session::new(&ctx, encoding);
+    print_lines(&session.show());
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim();
+        if line == "quit" || line == "exit" {
+            break;
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        match session.execute(line) {
+            Ok(lines) => print_lines(&lines),
+            Err(err) => println!("error: {}", err),
+        }
+    }
+
+    Ok(())
+}
+
+fn print_lines(lines: &[String]) {
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+/// A rule of the session, enforced while `active` through its guard literal
+struct Rule<'ctx> {
+    description: String,
+    guard: Bool<'ctx>,
+    active: bool,
+}
+
+/// One solver for the whole session. Rules are asserted as `guard => rule` and checked
+/// under the guards of the active ones, so dropping a rule needs no new solver.
+/// Every step pushes a scope, undo pops it and restores the rules as they were.
+struct Session<'ctx> {
+    ctx: &'ctx Context,
+    solver: Solver<'ctx>,
+    schedule: Schedule<'ctx>,
+    rules: Vec<Rule<'ctx>>,
+    /// Active flags before each step still on the solver stack
+    history: Vec<Vec<bool>>,
+}
+
+impl<'ctx> Session<'ctx> {
+    fn new(ctx: &'ctx Context, encoding: Encoding) -> Self {
+        let solver = Solver::new(ctx);
+        let schedule = Schedule::new(ctx, 4, encoding);
+        for constraint in schedule.domain() {
+            solver.assert(&constraint);
+        }
+
+        let mut session = Session {
+            ctx,
+            solver,
+            schedule,
+            rules: Vec::new(),
+            history: Vec::new(),
+        };
+        for (rule, text) in session.schedule.rules().iter().zip(RULES) {
+            session.guard(text.to_string(), rule);
+        }
+        session
+    }
+
+    /// Assert `rule` behind a fresh guard, active
+    fn guard(&mut self, description: String, rule: &Bool<'ctx>) {
+        let guard = ast::Bool::new_const(self.ctx, format!("rule{}", self.rules.len() + 1));
+        self.solver.assert(&guard.implies(rule));
+        self.rules.push(Rule {
+            description,
+            guard,
+            active: true,
+        });
+    }
+
+    /// Run one command, the lines to print
+    fn execute(&mut self, line: &str) -> anyhow::Result<Vec<String>> {
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        match command {
+            "add" => {
+                let requirement = self.requirement(rest)?;
+                let constraint = requirement.constraint(&self.schedule, None);
+                self.step();
+                self.guard(rest.to_string(), &constraint);
+            }
+            "drop" => {
+                let n: usize = rest
+                    .parse()
+                    .map_err(|_| anyhow!("expected a rule number, got `{}`", rest))?;
+                match self.rules.get(n.wrapping_sub(1)) {
+                    Some(rule) if rule.active => {}
+                    Some(_) => bail!("rule {} is already dropped", n),
+                    None => bail!("no rule {}, there are {}", n, self.rules.len()),
+                }
+                self.step();
+                self.rules[n - 1].active = false;
+            }
+            "pin" => {
+                let (meeting, places) = rest.split_once(' ').unwrap_or((rest, ""));
+                if places.trim().is_empty() {
+                    bail!("pin needs a meeting and where to keep it, like `pin M2 AM R1`");
+                }
+                let pins = places
+                    .split_whitespace()
+                    .map(|place| self.requirement(&format!("{} {}", meeting, place)))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                if pins.iter().any(|pin| pin.avoid) {
+                    bail!(
+                        "pin keeps a meeting somewhere, use `add {} !..` to keep it out",
+                        meeting
+                    );
+                }
+
+                let constraints: Vec<Bool> = pins
+                    .iter()
+                    .map(|pin| pin.constraint(&self.schedule, None))
+                    .collect();
+                let refs: Vec<&Bool> = constraints.iter().collect();
+                let pinned = ast::Bool::and(self.ctx, &refs);
+                self.step();
+                self.guard(format!("{} pinned to {}", meeting, places.trim()), &pinned);
+            }
+            "undo" => {
+                let Some(active) = self.history.pop() else {
+                    bail!("nothing to undo");
+                };
+                self.solver.pop(1);
+                self.rules.truncate(active.len());
+                for (rule, active) in self.rules.iter_mut().zip(active) {
+                    rule.active = active;
+                }
+            }
+            "rules" => return Ok(self.list()),
+            "show" | "check" => {}
+            "help" => return Ok(HELP.lines().map(str::to_string).collect()),
+            other => bail!("unknown command `{}`, try `help`", other),
+        }
+
+        Ok(self.show())
+    }
+
+    /// A requirement in the `--require` syntax, on one of the session's meetings
+    fn requirement(&self, text: &str) -> anyhow::Result<Preference> {
+        let requirement: Preference = text.parse()?;
+        if requirement
+            .meeting
+            .is_some_and(|m| m >= self.schedule.meetings())
+        {
+            bail!("no such meeting in `{}`", requirement);
+        }
+        Ok(requirement)
+    }
+
+    /// Open a scope for a step, remembering which rules were active
+    fn step(&mut self) {
+        self.history
+            .push(self.rules.iter().map(|rule| rule.active).collect());
+        self.solver.push();
+    }
+
+    fn list(&self) -> Vec<String> {
+        self.rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let dropped = if rule.active { "" } else { " (dropped)" };
+                format!("{}. {}{}", i + 1, rule.description, dropped)
+            })
+            .collect()
+    }
+
+    /// Check under the active rules: the schedule, or the rules (numbers) that clash
+    fn check(&self) -> Result<Vec<Solution>, Vec<usize>> {
+        let assumptions: Vec<Bool> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.active)
+            .map(|rule| rule.guard.clone())
+            .collect();
+
+        match self.solver.check_assumptions(&assumptions) {
+            SatResult::Sat => {
+                let model = self.solver.get_model().unwrap();
+                Ok(self.schedule.decode(&model))
+            }
+            _ => {
+                let core = self.solver.get_unsat_core();
+                Err((0..self.rules.len())
+                    .filter(|&i| core.contains(&self.rules[i].guard))
+                    .map(|i| i + 1)
+                    .collect())
+            }
+        }
+    }
+
+    fn show(&self) -> Vec<String> {
+        match self.check() {
+            Ok(solution) => {
+                let mut lines = vec!["---- SAT ----".to_string()];
+                lines.extend(
+                    solution
+                        .iter()
+                        .enumerate()
+                        .map(|(i, s)| format!("M{}: {}", i + 1, s)),
+                );
+                lines
+            }
+            Err(core) => {
+                let core: Vec<String> = core.iter().map(|n| n.to_string()).collect();
+                vec![
+                    "---- UNSAT ----".to_string(),
+                    format!("Conflicting rules: {}", core.join(", ")),
+                ]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_session() {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let mut session = Session::new(&ctx, Encoding::Int);
+    assert!(session.check().is_ok());
+
+    // Rule 1 wants M1 and M2 apart
+    session.execute("pin M1 PM").unwrap();
+    session.execute("pin M2 PM R1").unwrap();
+    let core = session.check().unwrap_err();
+    assert!(core.contains(&1) && core.contains(&9) && core.contains(&10));
+
+    session.execute("drop 1").unwrap();
+    let solution = session.check().unwrap();
+    assert!(!solution[0].am && !solution[1].am && solution[1].room == 1);
+
+    // Undo brings rule 1 back, then takes the pin away
+    session.execute("undo").unwrap();
+    assert!(session.check().is_err());
+    session.execute("undo").unwrap();
+    let solution = session.check().unwrap();
+    assert!(!solution[0].am && solution[1].am);
+    assert_eq!(session.list().len(), 9);
+
+    session.execute("add M3 R3").unwrap();
+    assert_eq!(session.check().unwrap()[2].room, 3);
+    assert_eq!(session.list()[9], "10. M3 R3");
+
+    assert!(session.execute("drop 42").is_err());
+    assert!(session.execute("pin M2 !AM").is_err());
+    assert!(session.execute("add M5 AM").is_err());
+    assert!(session.execute("pin M7 R1").is_err());
+    assert_eq!(session.list().len(), 10);
+    assert!(session.execute("frobnicate").is_err());
+    session.execute("undo").unwrap();
+    session.execute("undo").unwrap();
+    assert!(session.execute("undo").is_err());
+    assert_eq!(session.list().len(), 8);
+}
@@ -1,10 +1,10 @@
 use std::fmt;
 
-use duplicate::duplicate_item;
 use z3::{
     ast::{self, Ast, Bool, Int},
-    Context, Solver,
+    Context, Model, Solver,
 };
+use z3_enum_derive::Z3Enum;
 
 #[derive(Debug)]
 pub struct House<'ctx, 'solver> {
@@ -32,35 +32,83 @@ impl<'ctx, 'solver> House<'ctx, 'solver> {
         }
     }
 
-    // Strong Noir vibe;)
-    pub fn serialize(&self) -> Vec<&ast::Int> {
-        vec![
-            &self.index,
-            &self.color,
-            &self.nationality,
-            &self.beverage,
-            &self.cigar,
-            &self.pet,
-        ]
-    }
-
     /// Add a constraint A => B to the solver
     pub fn constrain_implication(&self, antecedant: &Bool, consequent: &Bool) {
         self.solver.assert(&antecedant.implies(consequent));
     }
 
+    /// Add a constraint index element is index neighbor - 1 (immediately left of it)
+    pub fn constrain_left_of(&self, other: &House, element: &Bool, neighbor: &Bool) {
+        self.solver
+            .assert(&(element & neighbor).implies(&self.left_of(other)));
+    }
+
     /// Add a constraint index element is +/- 1 index neighbor
     /// @dev do not forget these are constraints, so +/- 1 is done with z3 ast!
     pub fn constrain_next_to(&self, other: &House, element: &Bool, neighbor: &Bool) {
-        let one = ast::Int::from_i64(self.ctx, 1);
+        self.solver
+            .assert(&(element & neighbor).implies(&self.next_to(other)));
+    }
+
+    // The relations below are plain formulas over the indexes, to be used with
+    // constrain_implication, and `.not()` of any of them is its negated form.
+    // The Einstein clues don't need them all, test_relations covers each one.
 
-        self.solver.assert(&(element & neighbor).implies(
-            &(self.index._eq(&(&other.index + &one)) | self.index._eq(&(&other.index - &one))),
-        ));
+    /// Immediately left of `other`
+    pub fn left_of(&self, other: &House<'ctx, '_>) -> Bool<'ctx> {
+        self.apart_by(other, -1)
+    }
+
+    /// Immediately right of `other`
+    #[allow(dead_code)]
+    pub fn right_of(&self, other: &House<'ctx, '_>) -> Bool<'ctx> {
+        self.apart_by(other, 1)
+    }
+
+    /// Anywhere left of `other`
+    #[allow(dead_code)]
+    pub fn somewhere_left_of(&self, other: &House<'ctx, '_>) -> Bool<'ctx> {
+        self.index.lt(&other.index)
+    }
+
+    /// Anywhere right of `other`
+    #[allow(dead_code)]
+    pub fn somewhere_right_of(&self, other: &House<'ctx, '_>) -> Bool<'ctx> {
+        self.index.gt(&other.index)
+    }
+
+    /// Either neighbor of `other`
+    pub fn next_to(&self, other: &House<'ctx, '_>) -> Bool<'ctx> {
+        self.apart(other, 1)
+    }
+
+    /// Exactly `k` houses from `other`, on either side
+    pub fn apart(&self, other: &House<'ctx, '_>, k: i64) -> Bool<'ctx> {
+        self.apart_by(other, k) | self.apart_by(other, -k)
+    }
+
+    /// First or last of a street of `houses`
+    #[allow(dead_code)]
+    pub fn at_end(&self, houses: i64) -> Bool<'ctx> {
+        self.index._eq(&ast::Int::from_i64(self.ctx, 0))
+            | self.index._eq(&ast::Int::from_i64(self.ctx, houses - 1))
+    }
+
+    /// Anywhere between `one` and `other`, whichever of them is on the left
+    #[allow(dead_code)]
+    pub fn between(&self, one: &House<'ctx, '_>, other: &House<'ctx, '_>) -> Bool<'ctx> {
+        (self.somewhere_right_of(one) & self.somewhere_left_of(other))
+            | (self.somewhere_right_of(other) & self.somewhere_left_of(one))
+    }
+
+    /// index is index other + offset
+    fn apart_by(&self, other: &House<'ctx, '_>, offset: i64) -> Bool<'ctx> {
+        self.index
+            ._eq(&(&other.index + &ast::Int::from_i64(self.ctx, offset)))
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Z3Enum)]
 pub enum Color {
     Red,
     Green,
@@ -69,7 +117,7 @@ pub enum Color {
     White,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Z3Enum)]
 pub enum Nationality {
     Brit,
     Swede,
@@ -78,7 +126,7 @@ pub enum Nationality {
     German,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Z3Enum)]
 pub enum Beverage {
     Tea,
     Coffee,
@@ -87,16 +135,18 @@ pub enum Beverage {
     Water,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Z3Enum)]
 pub enum Cigar {
+    #[z3(name = "Pall Mall")]
     PallMall,
     Dunhill,
     Blend,
+    #[z3(name = "Blue Master")]
     BlueMaster,
     Prince,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Z3Enum)]
 pub enum Pet {
     Fish,
     Dogs,
@@ -105,89 +155,135 @@ pub enum Pet {
     Horses,
 }
 
-// marker trait, for all the enums which are `as i64`-able
-trait AsI64 {
-    fn as_i64(&self) -> i64;
+#[derive(Debug, PartialEq)]
+pub struct Solution {
+    pub color: Color,
+    pub nationality: Nationality,
+    pub beverage: Beverage,
+    pub cigar: Cigar,
+    pub pet: Pet,
 }
 
-impl<T: Copy> AsI64 for T 
-where 
-    T: Into<i64>
-{
-    fn as_i64(&self) -> i64 {
-        (*self).into()
+impl Solution {
+    /// The values of `house` in `model`, `None` if one is outside its enum
+    pub fn decode(model: &Model, house: &House) -> Option<Self> {
+        Some(Solution {
+            color: Color::from_model(model, &house.color)?,
+            nationality: Nationality::from_model(model, &house.nationality)?,
+            beverage: Beverage::from_model(model, &house.beverage)?,
+            cigar: Cigar::from_model(model, &house.cigar)?,
+            pet: Pet::from_model(model, &house.pet)?,
+        })
     }
 }
 
-pub trait ToZ3Int<'ctx> {
-    fn to_z3_int(&self, ctx: &'ctx Context) -> ast::Int<'ctx>;
-}
-
-impl<T> ToZ3Int<'_> for T
-where
-    T: Copy + AsI64,
-{
-    fn to_z3_int<'ctx>(&self, ctx: &'ctx Context) -> ast::Int<'ctx> {
-        ast::Int::from_i64(ctx, self.as_i64())
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.color)?;
+        writeln!(f, "{}", self.nationality)?;
+        writeln!(f, "{}", self.beverage)?;
+        writeln!(f, "{}", self.cigar)?;
+        writeln!(f, "{}", self.pet)
     }
 }
 
-#[duplicate_item(
-    enum_name      variant1     variant2    variant3      variant4       variant5;
-    [ Color ]      [ Red ]      [ Green ]   [ Yellow ]    [ Blue ]       [ White ];
-    [ Nationality] [ Brit ]     [ Swede ]   [ Dane ]      [ Norwegian ]  [ German ];
-    [ Beverage ]   [ Tea ]      [ Coffee ]  [ Milk ]      [ Beer ]       [ Water ];
-    [ Cigar ]      [ PallMall ] [ Dunhill ] [ Blend ]     [ BlueMaster ] [ Prince ];
-    [ Pet ]        [ Fish ]     [ Dogs ]    [ Birds ]     [ Cats ]       [ Horses ]
-)]
-impl enum_name {
-    pub fn from_int(value: i64) -> enum_name {
-        match value {
-            0 => enum_name::variant1,
-            1 => enum_name::variant2,
-            2 => enum_name::variant3,
-            3 => enum_name::variant4,
-            4 => enum_name::variant5,
-            _ => unreachable!("z3 constrained this"),
+#[cfg(test)]
+#[test]
+fn test_relations() {
+    use z3::{Config, SatResult};
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
+    let houses = 4;
+    let (a, b, c) = (
+        House::new(&ctx, &solver, 0),
+        House::new(&ctx, &solver, 1),
+        House::new(&ctx, &solver, 2),
+    );
+
+    // Every placement of three houses on a street of four, against plain evaluation
+    let placements = (0..houses)
+        .flat_map(|i| (0..houses).flat_map(move |j| (0..houses).map(move |k| (i, j, k))));
+    for (i, j, k) in placements {
+        if i == j || i == k || j == k {
+            continue;
         }
-    }
-}
+        solver.push();
+        solver.assert(&a.index._eq(&Int::from_i64(&ctx, i)));
+        solver.assert(&b.index._eq(&Int::from_i64(&ctx, j)));
+        solver.assert(&c.index._eq(&Int::from_i64(&ctx, k)));
+        assert_eq!(solver.check(), SatResult::Sat);
+        let model = solver.get_model().unwrap();
 
-#[duplicate_item(
-    enum_name      variant1     s1        variant2    s2       variant3   s3      variant4       s4          variant5   s5;
-    [ Color ]      [ Red ]      ["Red"]   [ Green ]   ["Green"][ Yellow ] ["Yellow"][ Blue ]     ["Blue"]    [ White ]  ["White"];
-    [ Nationality] [ Brit ]     ["Brit"]  [ Swede ]   ["Swede"][ Dane ]   ["Dane"] [ Norwegian ] ["Norwegian"][ German ] ["German"];
-    [ Beverage ]   [ Tea ]      ["Tea"]   [ Coffee ]  ["Coffee"][ Milk ]  ["Milk"] [ Beer ]      ["Beer"]    [ Water ]  ["Water"];
-    [ Cigar ]      [ PallMall ] ["Pall Mall"][ Dunhill ]["Dunhill"][ Blend ]["Blend"][ BlueMaster ]["Blue Master"][ Prince ]["Prince"];
-    [ Pet ]        [ Fish ]     ["Fish"]  [ Dogs ]    ["Dogs"] [ Birds ]  ["Birds"][ Cats ]      ["Cats"]    [ Horses ] ["Horses"]
-)]
-impl std::fmt::Display for enum_name {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            enum_name::variant1 => write!(f, s1),
-            enum_name::variant2 => write!(f, s2),
-            enum_name::variant3 => write!(f, s3),
-            enum_name::variant4 => write!(f, s4),
-            enum_name::variant5 => write!(f, s5),
+        let expected = [
+            ("left_of", a.left_of(&b), i + 1 == j),
+            ("right_of", a.right_of(&b), i == j + 1),
+            ("somewhere_left_of", a.somewhere_left_of(&b), i < j),
+            ("somewhere_right_of", a.somewhere_right_of(&b), i > j),
+            ("next_to", a.next_to(&b), (i - j).abs() == 1),
+            ("2 apart", a.apart(&b, 2), (i - j).abs() == 2),
+            ("at_end", a.at_end(houses), i == 0 || i == houses - 1),
+            (
+                "between",
+                a.between(&b, &c),
+                (j < i && i < k) || (k < i && i < j),
+            ),
+        ];
+        for (name, relation, holds) in expected {
+            for (formula, holds) in [(relation.clone(), holds), (relation.not(), !holds)] {
+                let value = model.eval(&formula, true).unwrap().as_bool().unwrap();
+                assert_eq!(value, holds, "{} with houses {} {} {}", name, i, j, k);
+            }
         }
+        solver.pop(1);
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Solution {
-    pub color: i64,
-    pub nationality: i64,
-    pub beverage: i64,
-    pub cigar: i64,
-    pub pet: i64,
-}
+#[cfg(test)]
+#[test]
+fn test_z3_enum() {
+    use z3::{ast::Datatype, Config, SatResult};
 
-impl fmt::Display for Solution {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "{}", Color::from_int(self.color))?;
-        writeln!(f, "{}", Nationality::from_int(self.nationality))?;
-        writeln!(f, "{}", Beverage::from_int(self.beverage))?;
-        writeln!(f, "{}", Cigar::from_int(self.cigar))?;
-        writeln!(f, "{}", Pet::from_int(self.pet))
+    assert_eq!(Cigar::VARIANTS.len(), 5);
+    assert_eq!(Cigar::BlueMaster.index(), 3);
+    assert_eq!(Cigar::from_index(0), Some(Cigar::PallMall));
+    assert_eq!(Cigar::from_index(5), None);
+    assert_eq!(Cigar::PallMall.to_string(), "Pall Mall");
+    assert_eq!(Pet::Fish.to_string(), "Fish");
+
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
+
+    // Three colors, all different, none of them red or green: yellow, blue and white
+    let colors: Vec<Int> = (0..3)
+        .map(|i| Int::new_const(&ctx, format!("color{}", i)))
+        .collect();
+    let refs: Vec<&Int> = colors.iter().collect();
+    solver.assert(&Color::z3_distinct(&ctx, &refs));
+    for color in &colors {
+        solver.assert(&Color::z3_bounds(color));
+        solver.assert(&color.gt(&Color::Green.to_z3_int(&ctx)));
     }
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    let mut decoded: Vec<Color> = colors
+        .iter()
+        .map(|color| Color::from_model(&model, color).unwrap())
+        .collect();
+    decoded.sort_by_key(Color::index);
+    assert_eq!(decoded, [Color::Yellow, Color::Blue, Color::White]);
+
+    // Out of the domain
+    let out = Int::from_i64(&ctx, 7);
+    assert_eq!(Color::from_model(&model, &out), None);
+
+    // As an enumeration sort the constants print their names
+    let (sort, constants, _) = Cigar::z3_sort(&ctx);
+    let cigar = Datatype::new_const(&ctx, "cigar", &sort);
+    solver.assert(&cigar._eq(&constants[0].apply(&[]).as_datatype().unwrap()));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    assert_eq!(model.eval(&cigar, true).unwrap().to_string(), "|Pall Mall|");
 }
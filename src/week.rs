@@ -0,0 +1,345 @@
+mod domain;
+mod scheduler;
+
+use std::env;
+
+use anyhow::{anyhow, bail, Context as _};
+use chrono::{Duration, NaiveDate};
+use chrono_tz::Tz;
+use scheduler::{
+    args::{flag_value, flag_values},
+    grid::Format,
+    schedule::room_name,
+    travel::{Building, Campus, Travel},
+    week::{self, Away, Booking, Limit, Plan, Series, Week},
+    zones::{self, Participant},
+};
+use z3::{Config, Context, SatResult, Solver};
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    println!(
+        "Plan a week of meetings over days x timeslots x rooms.
+        A recurring meeting (\"Standup every Tue and Thu\") keeps the same time and room on every occurrence,
+        a one-off meeting (\"Review once\") lands on whichever day fits.
+        Nobody attends two meetings at once, no room hosts two meetings at once,
+        meetings stay inside the local working hours of every attendee,
+        and back-to-back meetings leave their attendees time to walk between buildings."
+    );
+
+    // --series "Standup every Tue and Thu with alice, bob" --series "Review once with carol" ...
+    let series = flag_values(&args, "--series")
+        .iter()
+        .map(|s| s.parse::<Series>())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if series.is_empty() {
+        bail!("nothing to plan, add --series \"Standup every Tue and Thu with alice, bob\"");
+    }
+
+    // [--days Mon-Fri] [--slots 09:00,14:00] [--rooms 3]
+    let mut week = Week::default();
+    if let Some(days) = flag_value(&args, "--days") {
+        week.days = week::parse_days(&days)?;
+    }
+    if let Some(slots) = flag_value(&args, "--slots") {
+        week.slots = week::parse_slots(&slots)?;
+    }
+    if let Some(rooms) = flag_value(&args, "--rooms") {
+        week.rooms = rooms
+            .parse()
+            .map_err(|_| anyhow!("bad --rooms `{}`", rooms))?;
+    }
+
+    // [--zone Europe/Paris] [--week 2025-03-24] [--length 60]
+    // [--participant "alice=Europe/Paris 09:00-17:00" --participant "bob=America/New_York 09:00-17:00 Mon-Thu" ...]
+    if let Some(zone) = flag_value(&args, "--zone") {
+        week.zone = zone
+            .parse::<Tz>()
+            .map_err(|_| anyhow!("unknown time zone `{}`", zone))?;
+    }
+    if let Some(date) = flag_value(&args, "--week") {
+        week.date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .with_context(|| format!("bad --week `{}`", date))?;
+    }
+    if let Some(minutes) = flag_value(&args, "--length") {
+        week.length = Duration::minutes(
+            minutes
+                .parse()
+                .map_err(|_| anyhow!("bad --length `{}`, in minutes", minutes))?,
+        );
+    }
+    let participants = flag_values(&args, "--participant")
+        .iter()
+        .map(|p| p.parse::<Participant>())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let away = zones::away(&week, &participants);
+
+    // [--per-day 4] [--per-day alice=2 ...]
+    let limits = flag_values(&args, "--per-day")
+        .iter()
+        .map(|l| l.parse::<Limit>())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    // [--building North=R1,R2 --building South=R3 ...] [--travel North-South=15 ...]
+    let buildings = flag_values(&args, "--building")
+        .iter()
+        .map(|b| b.parse::<Building>())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let travel = flag_values(&args, "--travel")
+        .iter()
+        .map(|t| t.parse::<Travel>())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let campus = Campus::new(buildings, travel)?;
+    campus.check(week.rooms)?;
+
+    // [--grid text|markdown|html]
+    let format = match flag_value(&args, "--grid") {
+        Some(format) => format.parse()?,
+        None => Format::Text,
+    };
+
+    if let Some(bookings) = solve(&week, &series, &limits, &away, &campus)? {
+        println!("Grid ({}):", week.zone);
+        print!("{}", week::render(&week, &series, &bookings, format));
+
+        // With buildings, everybody's walks matter: attendees without a declared zone get the grid's
+        let mut agendas = participants.clone();
+        if !campus.is_empty() {
+            for person in series.iter().flat_map(|s| &s.attendees) {
+                if !agendas.iter().any(|p| &p.name == person) {
+                    agendas.push(Participant::anywhere(person, week.zone));
+                }
+            }
+        }
+        for participant in &agendas {
+            println!("{}:", participant);
+            for line in zones::agenda(&week, &series, &bookings, participant, &campus) {
+                println!("  {}", line);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn solve(
+    week: &Week,
+    series: &[Series],
+    limits: &[Limit],
+    away: &[Away],
+    campus: &Campus,
+) -> anyhow::Result<Option<Vec<Booking>>> {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
+
+    let plan = Plan::new(&ctx, series);
+    for constraint in plan.constraints(week, series, limits)? {
+        solver.assert(&constraint);
+    }
+    for constraint in plan.away(series, away) {
+        solver.assert(&constraint);
+    }
+    for constraint in plan.travel(week, series, campus) {
+        solver.assert(&constraint);
+    }
+
+    println!("Solving...");
+
+    if solver.check() == SatResult::Sat {
+        println!("---- SAT ----");
+
+        let model = solver.get_model().unwrap();
+        let bookings = plan.decode(&model, week);
+
+        println!("Model:");
+        for booking in &bookings {
+            println!(
+                "{}: {} {} {}",
+                series[booking.series].name,
+                booking.day,
+                week.slot_label(booking.slot),
+                room_name(booking.room)
+            );
+        }
+
+        Ok(Some(bookings))
+    } else {
+        println!("---- UNSAT ----");
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_week() {
+    use chrono::Weekday;
+
+    let series: Vec<Series> = [
+        "Standup every Tue and Thu with alice, bob",
+        "Planning every Mon with alice, carol",
+        "Review once with alice",
+        "Retro once with bob, carol",
+    ]
+    .iter()
+    .map(|s| s.parse().unwrap())
+    .collect();
+    let limits = [Limit {
+        person: Some("alice".to_string()),
+        max: 1,
+    }];
+    let week = Week {
+        rooms: 1,
+        ..Week::default()
+    };
+
+    let bookings = solve(&week, &series, &limits, &[], &Campus::default())
+        .unwrap()
+        .unwrap();
+    assert_eq!(bookings.len(), 5);
+
+    // Both standups at the same time in the same room
+    let standups: Vec<&Booking> = bookings.iter().filter(|b| b.series == 0).collect();
+    assert_eq!(
+        standups.iter().map(|b| b.day).collect::<Vec<_>>(),
+        [Weekday::Tue, Weekday::Thu]
+    );
+    assert_eq!(standups[0].slot, standups[1].slot);
+    assert_eq!(standups[0].room, standups[1].room);
+
+    // Alice already has a meeting on Mon, Tue and Thu
+    let review = bookings.iter().find(|b| b.series == 2).unwrap();
+    assert!([Weekday::Wed, Weekday::Fri].contains(&review.day));
+
+    // One room: no two meetings share a day and slot
+    for (i, a) in bookings.iter().enumerate() {
+        for b in &bookings[i + 1..] {
+            assert!(a.day != b.day || a.slot != b.slot);
+        }
+    }
+
+    // Three meetings a day is too much with a single slot and one-offs cannot fit anywhere else
+    let week = Week {
+        days: week::parse_days("Mon-Tue").unwrap(),
+        slots: week::parse_slots("09:00").unwrap(),
+        ..Week::default()
+    };
+    let series: Vec<Series> = ["A every Mon and Tue", "B once", "C once", "D once"]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+    let limits = [Limit {
+        person: None,
+        max: 2,
+    }];
+    assert!(solve(&week, &series, &limits, &[], &Campus::default())
+        .unwrap()
+        .is_none());
+    assert!(solve(&week, &series, &[], &[], &Campus::default())
+        .unwrap()
+        .is_some());
+}
+
+#[cfg(test)]
+#[test]
+fn test_week_time_zones() {
+    let series: Vec<Series> = ["Sync every Tue and Thu with alice, bob"]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+    let participants: Vec<Participant> = [
+        "alice=Europe/Paris 09:00-17:00",
+        "bob=America/New_York 09:00-17:00",
+    ]
+    .iter()
+    .map(|p| p.parse().unwrap())
+    .collect();
+
+    // Hourly UTC grid in January: Paris is UTC+1, New York UTC-5
+    let slots: Vec<String> = (0..24).map(|h| format!("{:02}:00", h)).collect();
+    let week = Week {
+        slots: week::parse_slots(&slots.join(",")).unwrap(),
+        ..Week::default()
+    };
+    let away = zones::away(&week, &participants);
+
+    let bookings = solve(&week, &series, &[], &away, &Campus::default())
+        .unwrap()
+        .unwrap();
+    let slot = week.slot_label(bookings[0].slot);
+    assert!(slot == "14:00" || slot == "15:00", "{}", slot);
+
+    let agenda = zones::agenda(
+        &week,
+        &series,
+        &bookings,
+        &participants[1],
+        &Campus::default(),
+    );
+    assert_eq!(agenda.len(), 2);
+    assert!(
+        agenda[0].starts_with("Tue 09:00-10:00 EST")
+            || agenda[0].starts_with("Tue 10:00-11:00 EST")
+    );
+
+    // Nobody is at work at the same time as someone in Sydney and in Paris
+    let participants: Vec<Participant> = [
+        "alice=Europe/Paris 09:00-17:00",
+        "bob=Australia/Sydney 09:00-17:00",
+    ]
+    .iter()
+    .map(|p| p.parse().unwrap())
+    .collect();
+    let away = zones::away(&week, &participants);
+    assert!(solve(&week, &series, &[], &away, &Campus::default())
+        .unwrap()
+        .is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn test_week_travel() {
+    // Two back-to-back slots on Monday, R1 in North and R2 in South
+    let mut week = Week {
+        days: week::parse_days("Mon").unwrap(),
+        slots: week::parse_slots("09:00,10:00").unwrap(),
+        rooms: 2,
+        ..Week::default()
+    };
+    let campus = Campus::new(
+        vec!["North=R1".parse().unwrap(), "South=R2".parse().unwrap()],
+        vec!["North-South=15".parse().unwrap()],
+    )
+    .unwrap();
+    let series: Vec<Series> = [
+        "A once with alice",
+        "B once with alice, bob",
+        "C once with bob",
+        "D once with carol",
+    ]
+    .iter()
+    .map(|s| s.parse().unwrap())
+    .collect();
+
+    // A, B and C would all have to be in the same building, which only has two cells
+    assert!(solve(&week, &series, &[], &[], &Campus::default())
+        .unwrap()
+        .is_some());
+    assert!(solve(&week, &series, &[], &[], &campus).unwrap().is_none());
+
+    // A quarter of an hour between meetings is enough to walk
+    week.slots = week::parse_slots("09:00,10:15").unwrap();
+    let bookings = solve(&week, &series, &[], &[], &campus).unwrap().unwrap();
+
+    let bob = Participant::anywhere("bob", week.zone);
+    let agenda = zones::agenda(&week, &series, &bookings, &bob, &campus);
+    assert_eq!(
+        agenda.len(),
+        2 + usize::from(bookings[1].room != bookings[2].room)
+    );
+    if agenda.len() == 3 {
+        assert!(agenda[1].starts_with("  15 min walk from"), "{:?}", agenda);
+    }
+    assert!(agenda[0].contains("North)") || agenda[0].contains("South)"));
+}
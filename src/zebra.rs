@@ -0,0 +1,210 @@
+mod domain;
+mod puzzle;
+mod scheduler;
+
+use std::{env, fs};
+
+use anyhow::{anyhow, Context as _};
+use puzzle::{
+    clues::Puzzle,
+    definition::EINSTEIN,
+    english, explain,
+    generate::Planted,
+    street::{Encoding, Solution, Street},
+    unique,
+};
+use scheduler::args::{flag_value, has_flag};
+use z3::{Config, Context, SatResult, Solver};
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    println!(
+        "Logic grid puzzles of any size: N houses in a row, categories of N values each,
+        every value in exactly one house, plus clues. Defaults to the Einstein puzzle."
+    );
+
+    // [puzzle.txt]
+    let mut puzzle = match args.first().filter(|arg| !arg.starts_with("--")) {
+        Some(path) => Puzzle::load(path)?,
+        None => EINSTEIN.parse()?,
+    };
+
+    // [--english clues.txt], the clues as sentences instead of the puzzle's own
+    if let Some(path) = flag_value(&args, "--english") {
+        let text = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+        let reading = english::read(&text, &puzzle.definition);
+        for (sentence, err) in &reading.unknown {
+            println!("Not understood: \"{}\" ({})", sentence, err);
+        }
+        puzzle.clues = reading.clues;
+    }
+
+    // [--generate [--houses 5] [--categories 5] [--seed N] [--save puzzle.txt]], a new puzzle
+    // with a single solution instead
+    if has_flag(&args, "--generate") {
+        let planted = generate(&args)?;
+        print!("{}", planted.text());
+        if let Some(path) = flag_value(&args, "--save") {
+            fs::write(&path, planted.text()).with_context(|| format!("writing {}", path))?;
+        }
+        puzzle = planted.puzzle;
+    } else {
+        print!("{}", puzzle.text());
+    }
+
+    // [--encoding int|distinct|bv|onehot|uf|enum]
+    let encoding = match flag_value(&args, "--encoding") {
+        Some(encoding) => encoding.parse()?,
+        None => Encoding::default(),
+    };
+
+    if let Some(solution) = solve(&puzzle, encoding, has_flag(&args, "--model")) {
+        print!("{}", solution.render(&puzzle.definition));
+    }
+
+    // [--explain], how the solution can be worked out by hand
+    if has_flag(&args, "--explain") {
+        println!("Deductions:");
+        for (i, step) in explain::explain(&puzzle)?.iter().enumerate() {
+            println!("{:>3}. {}", i + 1, step.text(&puzzle.definition));
+        }
+    }
+
+    // [--redundant], the clues the others imply
+    if has_flag(&args, "--redundant") {
+        redundant(&puzzle, encoding)?;
+    }
+
+    Ok(())
+}
+
+/// A random seed unless `--seed` is given, printed with the puzzle to get it back
+fn generate(args: &[String]) -> anyhow::Result<Planted> {
+    let number = |flag: &str, default: usize| match flag_value(args, flag) {
+        Some(n) => n.parse().map_err(|_| anyhow!("bad {} `{}`", flag, n)),
+        None => Ok(default),
+    };
+    let seed: u64 = match flag_value(args, "--seed") {
+        Some(seed) => seed.parse().map_err(|_| anyhow!("bad --seed `{}`", seed))?,
+        None => rand::random::<u32>().into(),
+    };
+
+    Planted::unique(seed, number("--houses", 5)?, number("--categories", 5)?)
+}
+
+/// Clues numbered from 1, as a reader counts them
+fn redundant(puzzle: &Puzzle, encoding: Encoding) -> anyhow::Result<()> {
+    let analysis = unique::analyze(puzzle, encoding)?;
+    let clue = |i: usize| format!("{:>3}. {}", i + 1, puzzle.clues[i].text(&puzzle.definition));
+
+    if analysis.redundant.is_empty() {
+        println!("No redundant clue, each one is needed for the solution");
+    } else {
+        println!("Redundant clues, the others force the same solution without them:");
+        for &i in &analysis.redundant {
+            println!("{}", clue(i));
+        }
+    }
+    println!(
+        "Minimal set, {} of {} clues:",
+        analysis.minimal.len(),
+        puzzle.clues.len()
+    );
+    for &i in &analysis.minimal {
+        println!("{}", clue(i));
+    }
+
+    Ok(())
+}
+
+/// With `print_model`, z3's own model is printed too (symbolic values with `Encoding::ENUM`)
+fn solve(puzzle: &Puzzle, encoding: Encoding, print_model: bool) -> Option<Solution> {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
+
+    let street = Street::new(&ctx, &puzzle.definition, encoding);
+    for constraint in street.constraints() {
+        solver.assert(&constraint);
+    }
+    for clue in &puzzle.clues {
+        for constraint in clue.constraints(&street) {
+            solver.assert(&constraint);
+        }
+    }
+
+    println!("Solving...");
+
+    if solver.check() == SatResult::Sat {
+        println!("---- SAT ----");
+        let model = solver.get_model().unwrap();
+        if print_model {
+            println!("Model:\n{}", model);
+        }
+        Some(street.decode(&model))
+    } else {
+        println!("---- UNSAT ----");
+        None
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_zebra() {
+    let puzzle: Puzzle = "houses: 7\ncolor: a, b, c, d, e, f, g\npet: h, i, j, k, l, m, n"
+        .parse()
+        .unwrap();
+    let solution = solve(&puzzle, Encoding::INT, false).unwrap();
+    assert_eq!(solution.houses.len(), 7);
+    for c in 0..2 {
+        let mut column: Vec<usize> = solution.houses.iter().map(|house| house[c]).collect();
+        column.sort();
+        assert_eq!(column, (0..7).collect::<Vec<_>>());
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_zebra_einstein() {
+    use puzzle::street::ENCODINGS;
+
+    let puzzle: Puzzle = EINSTEIN.parse().unwrap();
+    let solution = solve(&puzzle, Encoding::INT, false).unwrap();
+
+    // Same grid as einstein's test, values in definition order
+    assert_eq!(
+        solution.houses,
+        [
+            [2, 3, 4, 1, 3],
+            [3, 2, 0, 2, 4],
+            [0, 0, 2, 0, 2],
+            [1, 4, 1, 4, 0],
+            [4, 1, 3, 3, 1],
+        ]
+    );
+    let fish = puzzle.definition.find("Fish").unwrap();
+    let owner = solution.houses[solution.position(fish.0, fish.1)][1];
+    assert_eq!(puzzle.definition.value(1, owner), "German");
+    for encoding in ENCODINGS {
+        assert_eq!(
+            solve(&puzzle, encoding, false).as_ref(),
+            Some(&solution),
+            "{}",
+            encoding
+        );
+    }
+
+    // The Norwegian's only neighbor is blue
+    let mut contradiction = puzzle.clone();
+    contradiction
+        .clues
+        .push(puzzle::clues::Clue::parse("Norwegian next-to Red", &puzzle.definition).unwrap());
+    for encoding in ENCODINGS {
+        assert!(
+            solve(&contradiction, encoding, false).is_none(),
+            "{}",
+            encoding
+        );
+    }
+}
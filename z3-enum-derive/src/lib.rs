@@ -0,0 +1,151 @@
+//! `#[derive(Z3Enum)]`: a fieldless enum as a finite z3 domain.
+//!
+//! Variants are numbered in declaration order. The derive adds, as inherent items:
+//! - `VARIANTS`, `index`, `from_index` and `name` (`#[z3(name = "Pall Mall")]`, the variant
+//!   name otherwise), and a `Display` impl printing the name
+//! - `to_z3_int`, the variant as an `Int`
+//! - `z3_bounds` and `z3_distinct`, the domain constraints of `Int` variables
+//! - `from_model`, decoding an `Int` variable, `None` if it is out of the domain
+//! - `z3_sort`, an enumeration sort with one constant per variant, named after the enum
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr, Variant};
+
+#[proc_macro_derive(Z3Enum, attributes(z3))]
+pub fn derive_z3_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Z3Enum can only be derived for enums",
+        ));
+    };
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Z3Enum needs at least one variant",
+        ));
+    }
+
+    let mut variants = Vec::new();
+    let mut names = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "Z3Enum variants can't have fields",
+            ));
+        }
+        variants.push(&variant.ident);
+        names.push(name(variant)?);
+    }
+
+    let ident = &input.ident;
+    let sort = ident.to_string();
+    let count = variants.len();
+    let indices: Vec<i64> = (0..count as i64).collect();
+
+    Ok(quote! {
+        #[allow(dead_code)]
+        impl #ident {
+            /// Every variant, in declaration order
+            pub const VARIANTS: [#ident; #count] = [#(#ident::#variants),*];
+
+            /// Position of the variant in the declaration, its value in z3
+            pub fn index(&self) -> i64 {
+                match self {
+                    #(#ident::#variants => #indices,)*
+                }
+            }
+
+            pub fn from_index(index: i64) -> Option<Self> {
+                match index {
+                    #(#indices => Some(#ident::#variants),)*
+                    _ => None,
+                }
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    #(#ident::#variants => #names,)*
+                }
+            }
+
+            pub fn to_z3_int<'ctx>(&self, ctx: &'ctx z3::Context) -> z3::ast::Int<'ctx> {
+                z3::ast::Int::from_i64(ctx, self.index())
+            }
+
+            /// `var` is the index of some variant
+            pub fn z3_bounds<'ctx>(var: &z3::ast::Int<'ctx>) -> z3::ast::Bool<'ctx> {
+                use z3::ast::Ast;
+
+                let ctx = var.get_ctx();
+                var.ge(&z3::ast::Int::from_i64(ctx, 0))
+                    & var.lt(&z3::ast::Int::from_i64(ctx, #count as i64))
+            }
+
+            /// No two of `vars` are the same variant
+            pub fn z3_distinct<'ctx>(
+                ctx: &'ctx z3::Context,
+                vars: &[&z3::ast::Int<'ctx>],
+            ) -> z3::ast::Bool<'ctx> {
+                z3::ast::Ast::distinct(ctx, vars)
+            }
+
+            pub fn from_model<'ctx>(
+                model: &z3::Model<'ctx>,
+                var: &z3::ast::Int<'ctx>,
+            ) -> Option<Self> {
+                model.eval(var, true)?.as_i64().and_then(Self::from_index)
+            }
+
+            /// Enumeration sort named after the enum, with its constants and testers in
+            /// declaration order. The sort name must be unique in `ctx`, call it once per context.
+            pub fn z3_sort<'ctx>(
+                ctx: &'ctx z3::Context,
+            ) -> (
+                z3::Sort<'ctx>,
+                Vec<z3::FuncDecl<'ctx>>,
+                Vec<z3::FuncDecl<'ctx>>,
+            ) {
+                let names = [#(z3::Symbol::from(#names)),*];
+                z3::Sort::enumeration(ctx, z3::Symbol::from(#sort), &names)
+            }
+        }
+
+        impl std::fmt::Display for #ident {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+    })
+}
+
+/// `#[z3(name = "...")]`, or the variant name
+fn name(variant: &Variant) -> syn::Result<String> {
+    let mut name = variant.ident.to_string();
+
+    for attr in variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("z3"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("expected `name = \"...\"`"))
+            }
+        })?;
+    }
+
+    Ok(name)
+}
@@ -0,0 +1,63 @@
+use z3::{ast::Int, Config, Context, SatResult, Solver};
+use z3_enum_derive::Z3Enum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Z3Enum)]
+enum Drink {
+    Tea,
+    #[z3(name = "Hot chocolate")]
+    HotChocolate,
+    Water,
+}
+
+#[test]
+fn test_variants() {
+    assert_eq!(Drink::VARIANTS.len(), 3);
+    for (i, drink) in Drink::VARIANTS.iter().enumerate() {
+        assert_eq!(drink.index(), i as i64);
+        assert_eq!(Drink::from_index(drink.index()), Some(*drink));
+    }
+    assert_eq!(Drink::from_index(-1), None);
+    assert_eq!(Drink::from_index(3), None);
+
+    assert_eq!(Drink::Tea.name(), "Tea");
+    assert_eq!(Drink::HotChocolate.name(), "Hot chocolate");
+    assert_eq!(Drink::HotChocolate.to_string(), "Hot chocolate");
+}
+
+#[test]
+fn test_model() {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
+
+    // Two different drinks, neither of them tea, the first one before the second
+    let drinks: Vec<Int> = (0..2)
+        .map(|i| Int::new_const(&ctx, format!("drink{}", i)))
+        .collect();
+    let refs: Vec<&Int> = drinks.iter().collect();
+    solver.assert(&Drink::z3_distinct(&ctx, &refs));
+    for drink in &drinks {
+        solver.assert(&Drink::z3_bounds(drink));
+        solver.assert(&drink.gt(&Drink::Tea.to_z3_int(&ctx)));
+    }
+    solver.assert(&drinks[0].lt(&drinks[1]));
+
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    assert_eq!(
+        Drink::from_model(&model, &drinks[0]),
+        Some(Drink::HotChocolate)
+    );
+    assert_eq!(Drink::from_model(&model, &drinks[1]), Some(Drink::Water));
+    assert_eq!(Drink::from_model(&model, &Int::from_i64(&ctx, 5)), None);
+
+    // Without the bounds a third distinct drink has nowhere to go but out of the domain
+    let third = Int::new_const(&ctx, "drink2");
+    solver.assert(&Drink::z3_distinct(&ctx, &[&drinks[0], &drinks[1], &third]));
+    solver.assert(&third.gt(&Drink::Tea.to_z3_int(&ctx)));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    assert_eq!(Drink::from_model(&model, &third), None);
+    solver.assert(&Drink::z3_bounds(&third));
+    assert_eq!(solver.check(), SatResult::Unsat);
+}